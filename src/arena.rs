@@ -0,0 +1,390 @@
+//! The fighting ground seen from above, measured in feet.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+/// A spot or offset on the ground. Side 0 lines up along `x = 0` facing +x; side 1 at
+/// `x = start_distance` facing back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn length(self) -> f32 {
+        self.x.hypot(self.y)
+    }
+
+    pub fn distance(self, other: Point) -> f32 {
+        (other - self).length()
+    }
+
+    pub fn dot(self, other: Point) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The unit vector pointing the same way; `None` for a zero offset.
+    pub fn normalized(self) -> Option<Point> {
+        let length = self.length();
+        (length > f32::EPSILON).then(|| self * (1.0 / length))
+    }
+
+    /// Unit vector from this spot toward `other`, if they are apart.
+    pub fn direction_to(self, other: Point) -> Option<Point> {
+        (other - self).normalized()
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other: Point) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, other: Point) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<f32> for Point {
+    type Output = Point;
+
+    fn mul(self, scale: f32) -> Point {
+        Point::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+/// How far fighters keep from a wall's corner as they walk around it.
+const CLEARANCE_FEET: f32 = 1.0;
+/// A shooter this close to cover shoots over it rather than into it.
+const SHOOT_OVER_FEET: f32 = 5.0;
+
+/// A region of the ground.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Area {
+    /// A box between two opposite corners, sides along the axes.
+    Rect { min: Point, max: Point },
+    /// A round area, such as a pillar.
+    Circle { center: Point, radius: f32 },
+}
+
+impl Area {
+    pub fn contains(self, point: Point) -> bool {
+        match self {
+            Area::Rect { min, max } => {
+                (min.x..=max.x).contains(&point.x) && (min.y..=max.y).contains(&point.y)
+            }
+            Area::Circle { center, radius } => center.distance(point) <= radius,
+        }
+    }
+
+    /// How far the point is from the area's edge; 0 inside it.
+    pub fn distance_to(self, point: Point) -> f32 {
+        match self {
+            Area::Rect { min, max } => Point::new(
+                (min.x - point.x).max(point.x - max.x).max(0.0),
+                (min.y - point.y).max(point.y - max.y).max(0.0),
+            )
+            .length(),
+            Area::Circle { center, radius } => (center.distance(point) - radius).max(0.0),
+        }
+    }
+
+    /// The smallest box holding the area, as its two opposite corners.
+    pub fn bounds(self) -> (Point, Point) {
+        match self {
+            Area::Rect { min, max } => (min, max),
+            Area::Circle { center, radius } => (
+                center - Point::new(radius, radius),
+                center + Point::new(radius, radius),
+            ),
+        }
+    }
+
+    /// How far along the segment, from 0 to 1, it first enters the area; 0 if it starts
+    /// inside, `None` if it never touches it.
+    pub fn entry(self, from: Point, to: Point) -> Option<f32> {
+        if self.contains(from) {
+            return Some(0.0);
+        }
+        let delta = to - from;
+        match self {
+            Area::Rect { min, max } => {
+                // Clip the segment against each pair of sides in turn.
+                let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+                for (start, step, low, high) in [
+                    (from.x, delta.x, min.x, max.x),
+                    (from.y, delta.y, min.y, max.y),
+                ] {
+                    if step.abs() <= f32::EPSILON {
+                        if start < low || start > high {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let (near, far) = ((low - start) / step, (high - start) / step);
+                    enter = enter.max(near.min(far));
+                    exit = exit.min(near.max(far));
+                }
+                (enter <= exit).then_some(enter)
+            }
+            Area::Circle { center, radius } => {
+                let length_sq = delta.dot(delta);
+                if length_sq <= f32::EPSILON {
+                    return None;
+                }
+                let offset = from - center;
+                let half_b = offset.dot(delta);
+                let c = offset.dot(offset) - radius * radius;
+                let discriminant = half_b * half_b - length_sq * c;
+                if discriminant < 0.0 {
+                    return None;
+                }
+                let enter = (-half_b - discriminant.sqrt()) / length_sq;
+                (0.0..=1.0).contains(&enter).then_some(enter)
+            }
+        }
+    }
+}
+
+/// What a patch of ground does to the fighters on it or shooting across it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TerrainKind {
+    /// Mud, rubble or brush; movement starting on it is multiplied by `movement`.
+    Difficult {
+        #[serde(default = "default_difficult_movement")]
+        movement: f32,
+    },
+    /// Walls and pillars: nobody walks through them and nothing is shot past them.
+    Wall,
+    /// A low wall, hedge or barricade. Like a shield's cover value, it caps the ranged
+    /// attack roll against anyone it stands between the shooter and.
+    Cover { cover_value: i32 },
+}
+
+fn default_difficult_movement() -> f32 {
+    0.5
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TerrainFeature {
+    #[serde(flatten)]
+    pub kind: TerrainKind,
+    pub area: Area,
+}
+
+/// The terrain laid over the field. Open ground everywhere else.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Arena {
+    pub terrain: Vec<TerrainFeature>,
+}
+
+impl Arena {
+    pub fn new(terrain: Vec<TerrainFeature>) -> Self {
+        Self { terrain }
+    }
+
+    fn walls(&self) -> impl Iterator<Item = Area> + '_ {
+        self.terrain
+            .iter()
+            .filter(|feature| feature.kind == TerrainKind::Wall)
+            .map(|feature| feature.area)
+    }
+
+    /// Movement multiplier for a fighter setting off from `point`; the worst ground
+    /// under them counts.
+    pub fn movement_at(&self, point: Point) -> f32 {
+        self.terrain
+            .iter()
+            .filter_map(|feature| match feature.kind {
+                TerrainKind::Difficult { movement } if feature.area.contains(point) => {
+                    Some(movement.max(0.0))
+                }
+                _ => None,
+            })
+            .fold(1.0, f32::min)
+    }
+
+    /// The first wall the segment runs into and how far along it does, ignoring walls
+    /// `from` already stands in so nobody is trapped.
+    fn first_wall(&self, from: Point, to: Point) -> Option<(Area, f32)> {
+        self.walls()
+            .filter(|wall| !wall.contains(from))
+            .filter_map(|wall| Some((wall, wall.entry(from, to)?)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Where a fighter walking from `from` toward `to` ends up: stopped just short of
+    /// the first wall in the way.
+    pub fn walk(&self, from: Point, to: Point) -> Point {
+        match self.first_wall(from, to) {
+            Some((_, entry)) => {
+                let length = from.distance(to);
+                let back_off = (entry - 0.01 / length.max(f32::EPSILON)).max(0.0);
+                from + (to - from) * back_off
+            }
+            None => to,
+        }
+    }
+
+    /// Whether a shot or blow from `from` reaches `to` without a wall in between.
+    pub fn line_of_fire(&self, from: Point, to: Point) -> bool {
+        self.first_wall(from, to).is_none()
+    }
+
+    /// The lowest cover value of the cover between a shooter and their target. Cover
+    /// the shooter stands at does not count; they shoot over it.
+    pub fn cover(&self, from: Point, to: Point) -> Option<i32> {
+        self.terrain
+            .iter()
+            .filter_map(|feature| match feature.kind {
+                TerrainKind::Cover { cover_value }
+                    if feature.area.distance_to(from) > SHOOT_OVER_FEET
+                        && feature.area.entry(from, to).is_some() =>
+                {
+                    Some(cover_value)
+                }
+                _ => None,
+            })
+            .min()
+    }
+
+    /// Unit vector to walk along from `from` to reach `to`. With a wall in the way, heads
+    /// for whichever of its corners in sight makes the shorter way round.
+    pub fn heading(&self, from: Point, to: Point) -> Option<Point> {
+        let Some((wall, _)) = self.first_wall(from, to) else {
+            return from.direction_to(to);
+        };
+        let (min, max) = wall.bounds();
+        let (min, max) = (
+            min - Point::new(CLEARANCE_FEET, CLEARANCE_FEET),
+            max + Point::new(CLEARANCE_FEET, CLEARANCE_FEET),
+        );
+        [min, Point::new(min.x, max.y), max, Point::new(max.x, min.y)]
+            .into_iter()
+            .filter(|&corner| {
+                from.distance(corner) > f32::EPSILON && self.line_of_fire(from, corner)
+            })
+            .min_by(|&a, &b| {
+                let way_round = |corner: Point| from.distance(corner) + corner.distance(to);
+                way_round(a).total_cmp(&way_round(b))
+            })
+            .map_or_else(|| from.direction_to(to), |corner| from.direction_to(corner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pillar() -> TerrainFeature {
+        TerrainFeature {
+            kind: TerrainKind::Wall,
+            area: Area::Circle {
+                center: Point::new(10.0, 0.0),
+                radius: 2.0,
+            },
+        }
+    }
+
+    #[test]
+    fn segments_enter_areas_where_they_first_touch_them() {
+        let rect = Area::Rect {
+            min: Point::new(4.0, -1.0),
+            max: Point::new(6.0, 1.0),
+        };
+        assert_eq!(
+            rect.entry(Point::new(0.0, 0.0), Point::new(10.0, 0.0)),
+            Some(0.4)
+        );
+        assert_eq!(
+            rect.entry(Point::new(0.0, 2.0), Point::new(10.0, 2.0)),
+            None
+        );
+        assert_eq!(
+            rect.entry(Point::new(5.0, 0.0), Point::new(10.0, 0.0)),
+            Some(0.0)
+        );
+        let circle = pillar().area;
+        assert_eq!(
+            circle.entry(Point::new(0.0, 0.0), Point::new(20.0, 0.0)),
+            Some(0.4)
+        );
+        assert_eq!(
+            circle.entry(Point::new(0.0, 0.0), Point::new(5.0, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn walls_stop_walkers_who_head_round_them() {
+        let arena = Arena::new(vec![pillar()]);
+        let (from, to) = (Point::new(0.0, 0.0), Point::new(20.0, 0.0));
+        assert!(!arena.line_of_fire(from, to));
+        let stop = arena.walk(from, to);
+        assert!(stop.x < 8.0 && stop.x > 7.9, "{stop:?}");
+        let heading = arena.heading(from, to).unwrap();
+        assert!(heading.x > 0.0 && heading.y.abs() > 0.1, "{heading:?}");
+        assert!(arena.line_of_fire(from, from + heading * 10.0));
+        assert_eq!(
+            arena.heading(from, Point::new(0.0, 5.0)),
+            Some(Point::new(0.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn terrain_reads_from_scenario_json() {
+        let arena: Arena = serde_json::from_str(
+            r#"[
+                { "kind": "difficult", "area": { "circle": { "center": { "x": 0, "y": 0 }, "radius": 5 } } },
+                { "kind": "cover", "cover_value": 11, "area": { "rect": { "min": { "x": 8, "y": -5 }, "max": { "x": 9, "y": 5 } } } }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(arena.movement_at(Point::new(1.0, 1.0)), 0.5);
+        assert_eq!(arena.movement_at(Point::new(6.0, 0.0)), 1.0);
+        // The shooter right behind the cover shoots over it.
+        assert_eq!(
+            arena.cover(Point::new(20.0, 0.0), Point::new(0.0, 0.0)),
+            Some(11)
+        );
+        assert_eq!(
+            arena.cover(Point::new(10.0, 0.0), Point::new(0.0, 0.0)),
+            None
+        );
+    }
+}
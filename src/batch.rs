@@ -0,0 +1,284 @@
+use crate::arena::Arena;
+use crate::critical::CriticalTable;
+use crate::sim::{Combatant, FightStats, SimConfig, SimState, fight_stats};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct BatchConfig {
+    pub runs: u32,
+    pub base_seed: u64,
+    pub max_seconds: u32,
+}
+
+impl BatchConfig {
+    pub fn new(runs: u32, base_seed: u64) -> Self {
+        Self {
+            runs,
+            base_seed,
+            max_seconds: 600,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DuelResult {
+    pub seed: u64,
+    pub winner: Option<usize>,
+    pub timed_out: bool,
+    pub seconds: u32,
+    pub stats: [FightStats; 2],
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Distribution {
+    pub mean: f64,
+    pub median: f64,
+    pub p10: f64,
+    pub p25: f64,
+    pub p75: f64,
+    pub p90: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BatchSummary {
+    pub runs: usize,
+    pub wins: [usize; 2],
+    pub draws: usize,
+    pub timeouts: usize,
+    pub time_to_kill: Option<Distribution>,
+    pub hit_rate: [f64; 2],
+    pub avg_damage_per_hit: [f64; 2],
+    pub shield_break_rate: [f64; 2],
+}
+
+impl BatchSummary {
+    pub fn win_rate(&self, side: usize) -> f64 {
+        ratio(self.wins[side] as f64, self.runs as f64)
+    }
+
+    pub fn draw_rate(&self) -> f64 {
+        ratio(self.draws as f64, self.runs as f64)
+    }
+
+    pub fn timeout_rate(&self) -> f64 {
+        ratio(self.timeouts as f64, self.runs as f64)
+    }
+}
+
+/// Fights the same pair `runs` times, seeding run `i` with `base_seed + i`.
+pub fn run_batch(
+    config: SimConfig,
+    critical_table: &CriticalTable,
+    combatants: &[Combatant; 2],
+    batch: &BatchConfig,
+) -> Vec<DuelResult> {
+    let [first, second] = combatants.clone();
+    run_battle_batch(
+        config,
+        critical_table,
+        &Arena::default(),
+        &[vec![first], vec![second]],
+        batch,
+    )
+}
+
+/// Like `run_batch`, for two sides of any size on the given terrain; results count wins
+/// by side.
+pub fn run_battle_batch(
+    config: SimConfig,
+    critical_table: &CriticalTable,
+    arena: &Arena,
+    sides: &[Vec<Combatant>; 2],
+    batch: &BatchConfig,
+) -> Vec<DuelResult> {
+    let mut sim = SimState::new(config)
+        .with_critical_table(critical_table.clone())
+        .with_arena(arena.clone());
+    sim.reset_with_sides(sides.clone());
+    (0..batch.runs)
+        .map(|run| {
+            sim.reset_with_seed(batch.base_seed.wrapping_add(run as u64));
+            run_duel(&mut sim, batch.max_seconds)
+        })
+        .collect()
+}
+
+pub fn run_duel(sim: &mut SimState, max_seconds: u32) -> DuelResult {
+    while !sim.done && sim.elapsed_seconds < max_seconds {
+        sim.tick();
+    }
+    DuelResult {
+        seed: sim.seed(),
+        winner: sim.winner(),
+        timed_out: !sim.done,
+        seconds: sim.elapsed_seconds,
+        stats: fight_stats(&sim.combat_log, &sim.sides()),
+    }
+}
+
+pub fn summarize(results: &[DuelResult]) -> BatchSummary {
+    let mut summary = BatchSummary {
+        runs: results.len(),
+        ..Default::default()
+    };
+    let mut kill_times = Vec::new();
+    let mut totals = [FightStats::default(); 2];
+    let mut shield_breaks = [0usize; 2];
+    for result in results {
+        if result.timed_out {
+            summary.timeouts += 1;
+        } else if let Some(winner) = result.winner {
+            summary.wins[winner] += 1;
+            kill_times.push(result.seconds as f64);
+        } else {
+            summary.draws += 1;
+        }
+        for side in 0..2 {
+            let stats = result.stats[side];
+            totals[side].attacks += stats.attacks;
+            totals[side].hits += stats.hits;
+            totals[side].damage_dealt += stats.damage_dealt;
+            if stats.shields_broken > 0 {
+                // Side `side` broke its opponent's shield.
+                shield_breaks[1 - side] += 1;
+            }
+        }
+    }
+    summary.time_to_kill = distribution(&mut kill_times);
+    for side in 0..2 {
+        summary.hit_rate[side] = ratio(totals[side].hits as f64, totals[side].attacks as f64);
+        summary.avg_damage_per_hit[side] =
+            ratio(totals[side].damage_dealt as f64, totals[side].hits as f64);
+        summary.shield_break_rate[side] = ratio(shield_breaks[side] as f64, results.len() as f64);
+    }
+    summary
+}
+
+fn distribution(values: &mut [f64]) -> Option<Distribution> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    Some(Distribution {
+        mean,
+        median: percentile(values, 50.0),
+        p10: percentile(values, 10.0),
+        p25: percentile(values, 25.0),
+        p75: percentile(values, 75.0),
+        p90: percentile(values, 90.0),
+        min: values[0],
+        max: values[values.len() - 1],
+    })
+}
+
+/// Nearest-rank percentile over an already sorted slice.
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duelist(name: &str) -> Combatant {
+        Combatant {
+            name: name.to_string(),
+            weapon_name: "Test Blade".to_string(),
+            damage_expr: "2d6p".to_string(),
+            has_weapon: true,
+            max_hp: 20,
+            hp: 20,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn batch_outcomes_cover_every_run() {
+        let combatants = [duelist("A"), duelist("B")];
+        let results = run_batch(
+            SimConfig::new(10.0, 1.0),
+            &CriticalTable::default(),
+            &combatants,
+            &BatchConfig::new(50, 7),
+        );
+        let summary = summarize(&results);
+        assert_eq!(summary.runs, 50);
+        assert_eq!(
+            summary.wins[0] + summary.wins[1] + summary.draws + summary.timeouts,
+            50
+        );
+        assert!(summary.time_to_kill.is_some());
+    }
+
+    #[test]
+    fn batch_runs_use_distinct_seeds_and_replay() {
+        let combatants = [duelist("A"), duelist("B")];
+        let batch = BatchConfig::new(5, 100);
+        let table = CriticalTable::default();
+        let first = run_batch(SimConfig::new(10.0, 1.0), &table, &combatants, &batch);
+        let second = run_batch(SimConfig::new(10.0, 1.0), &table, &combatants, &batch);
+        let seeds: Vec<u64> = first.iter().map(|result| result.seed).collect();
+        assert_eq!(seeds, vec![100, 101, 102, 103, 104]);
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a.winner, b.winner);
+            assert_eq!(a.seconds, b.seconds);
+        }
+    }
+
+    #[test]
+    fn summary_rates_and_percentiles() {
+        let stats = |attacks, hits, damage, broken| FightStats {
+            attacks,
+            hits,
+            shield_blocks: 0,
+            damage_dealt: damage,
+            shields_broken: broken,
+            ..Default::default()
+        };
+        let results = vec![
+            DuelResult {
+                seed: 1,
+                winner: Some(0),
+                timed_out: false,
+                seconds: 10,
+                stats: [stats(4, 2, 20, 1), stats(4, 1, 5, 0)],
+            },
+            DuelResult {
+                seed: 2,
+                winner: Some(1),
+                timed_out: false,
+                seconds: 30,
+                stats: [stats(4, 2, 10, 0), stats(4, 3, 30, 0)],
+            },
+            DuelResult {
+                seed: 3,
+                winner: None,
+                timed_out: true,
+                seconds: 600,
+                stats: [FightStats::default(), FightStats::default()],
+            },
+        ];
+        let summary = summarize(&results);
+        assert_eq!(summary.wins, [1, 1]);
+        assert_eq!(summary.timeouts, 1);
+        assert!((summary.hit_rate[0] - 0.5).abs() < 1e-9);
+        assert!((summary.avg_damage_per_hit[1] - 8.75).abs() < 1e-9);
+        assert!((summary.shield_break_rate[1] - 1.0 / 3.0).abs() < 1e-9);
+        let ttk = summary.time_to_kill.unwrap();
+        assert!((ttk.mean - 20.0).abs() < 1e-9);
+        assert_eq!(ttk.median, 10.0);
+        assert_eq!(ttk.max, 30.0);
+    }
+}
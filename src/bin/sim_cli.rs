@@ -1,180 +1,495 @@
-#[path = "../character.rs"]
-mod character;
-#[path = "../sim.rs"]
-mod sim;
-#[path = "../game_logic.rs"]
-#[allow(dead_code)]
-mod game_logic;
-
-use character::{
-    AbilityScore, AbilitySet, ArmorRegion, Character, Equipment, MaterialKind, Progression,
-    ProgressionTier, Weapon, WeaponGroup, WeaponMastery,
-};
-use sim::{Combatant, SimConfig, SimState};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use hackmaster_sim::arena::Arena;
+use hackmaster_sim::batch::{self, BatchConfig, BatchSummary, DuelResult};
+use hackmaster_sim::critical::CriticalTable;
+use hackmaster_sim::export;
+use hackmaster_sim::fighting_style::FightingStyle;
+use hackmaster_sim::maneuver::Stance;
+use hackmaster_sim::scenario::{self, Catalogs};
+use hackmaster_sim::sim::{Combatant, SimConfig, SimState};
+use hackmaster_sim::tactics::TargetPriority;
+
+const USAGE: &str = "\
+Usage: sim_cli [SCENARIO] [options]
+
+Runs the duel described by SCENARIO (a JSON scenario file). Without a
+scenario, data/scenarios/example_duel.json is used. Each fighter is either
+an inline build or {\"build\": \"path/to/build.json\"}, relative to SCENARIO.
+A scenario lists two \"fighters\" for a duel, or two \"sides\" of any size
+for a battle, which ends when one side is down. An optional \"terrain\"
+list lays walls, cover and difficult ground over the field.
+
+Options:
+  -n, --runs N          Number of duels to fight (default 1)
+  -s, --seed SEED       Override the scenario seed; batch run i uses SEED + i
+  -f, --format FORMAT   text, json or jsonl (default text)
+  -o, --output PATH     Write to PATH instead of stdout
+      --max-seconds N   Call a duel a timeout after N seconds (default 600)
+  -v, --verbose         Also print combatant sheets and per-duel lines
+  -q, --quiet           Only print the outcome, or a batch's run count and win,
+                        draw and timeout rates
+  -h, --help            Show this help";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+    Jsonl,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct CliOptions {
+    scenario: Option<PathBuf>,
+    runs: u32,
+    seed: Option<u64>,
+    format: OutputFormat,
+    output: Option<PathBuf>,
+    max_seconds: u32,
+    verbosity: u8,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        Self {
+            scenario: None,
+            runs: 1,
+            seed: None,
+            format: OutputFormat::Text,
+            output: None,
+            max_seconds: 600,
+            verbosity: 1,
+        }
+    }
+}
 
 fn main() {
-    let abilities = AbilitySet {
-        strength: AbilityScore::new(15, 1), // 15/01
-        intelligence: 12,
-        wisdom: 11,
-        dexterity: AbilityScore::new(13, 1),
-        constitution: 14,
-        looks: 10,
-        charisma: 10,
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(err) => {
+            eprintln!("sim_cli: {err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
     };
+    if let Err(err) = run(&options) {
+        eprintln!("sim_cli: {err}");
+        std::process::exit(1);
+    }
+}
 
-    let weapon = Weapon {
-        name: "Longsword".to_string(),
-        group: WeaponGroup::LargeSwords,
-        speed: 10.0,
-        damage_expr: "2d8p".to_string(),
-        reach_ft: 3.5,
-        armor_pen: 0,
-        defense_bonus_always: false,
+/// Returns `None` when help was requested.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<CliOptions>, String> {
+    let mut options = CliOptions::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or_else(|| format!("{flag} needs a value"));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-n" | "--runs" => {
+                options.runs = parse_number(&arg, &value(&arg)?)?;
+                if options.runs == 0 {
+                    return Err(format!("{arg} must be at least 1"));
+                }
+            }
+            "-s" | "--seed" => options.seed = Some(parse_number(&arg, &value(&arg)?)?),
+            "-f" | "--format" => {
+                options.format = match value(&arg)?.as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    "jsonl" => OutputFormat::Jsonl,
+                    other => return Err(format!("unknown format {other:?}")),
+                }
+            }
+            "-o" | "--output" => options.output = Some(PathBuf::from(value(&arg)?)),
+            "--max-seconds" => options.max_seconds = parse_number(&arg, &value(&arg)?)?,
+            "-v" | "--verbose" => options.verbosity = options.verbosity.saturating_add(1),
+            flag if flag.len() > 2 && flag[1..].bytes().all(|byte| byte == b'v') => {
+                options.verbosity = options.verbosity.saturating_add(flag.len() as u8 - 1);
+            }
+            "-q" | "--quiet" => options.verbosity = 0,
+            flag if flag.starts_with('-') => return Err(format!("unknown option {flag}")),
+            path => {
+                if options.scenario.is_some() {
+                    return Err(format!("unexpected argument {path}"));
+                }
+                options.scenario = Some(PathBuf::from(path));
+            }
+        }
+    }
+    Ok(Some(options))
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag} expects a number, got {value:?}"))
+}
+
+fn run(options: &CliOptions) -> Result<(), String> {
+    let scenario = match &options.scenario {
+        Some(path) => scenario::load_scenario(path)?,
+        None => scenario::parse_scenario(scenario::EXAMPLE_SCENARIO_JSON)?,
+    };
+    let catalogs = Catalogs::load()?;
+    let combatants = scenario.combatants(&catalogs)?;
+    let mut config = scenario.sim_config(&catalogs)?;
+    if let Some(seed) = options.seed {
+        config.seed = seed;
+    }
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|err| format!("{}: {err}", path.display()))?,
+        )),
+        None => Box::new(io::stdout().lock()),
+    };
+    let result = if options.runs == 1 {
+        run_single(
+            &mut out,
+            options,
+            config,
+            &catalogs.critical_table,
+            &scenario.arena,
+            combatants,
+        )
+    } else {
+        run_many(
+            &mut out,
+            options,
+            config,
+            &catalogs.critical_table,
+            &scenario.arena,
+            &combatants,
+        )
     };
-    let reach_ft = weapon.reach_ft;
+    result
+        .and_then(|()| out.flush())
+        .map_err(|err| err.to_string())
+}
+
+fn run_single(
+    out: &mut impl Write,
+    options: &CliOptions,
+    config: SimConfig,
+    critical_table: &CriticalTable,
+    arena: &Arena,
+    combatants: [Vec<Combatant>; 2],
+) -> io::Result<()> {
+    let mut sim = SimState::new(config)
+        .with_critical_table(critical_table.clone())
+        .with_arena(arena.clone());
+    sim.reset_with_sides(combatants.clone());
+    let duel = batch::run_duel(&mut sim, options.max_seconds);
+    match options.format {
+        OutputFormat::Jsonl => export::write_log_jsonl(out, &sim.combat_log),
+        OutputFormat::Json => {
+            let batch_config = BatchConfig {
+                max_seconds: options.max_seconds,
+                ..BatchConfig::new(1, config.seed)
+            };
+            write_report(out, config, batch_config, &combatants, &[duel])
+        }
+        OutputFormat::Text => {
+            if options.verbosity >= 2 {
+                write_combatants(out, &combatants)?;
+            }
+            if options.verbosity >= 1 {
+                writeln!(out, "--- Simulation (1s ticks, seed {}) ---", sim.seed())?;
+                for entry in &sim.combat_log {
+                    writeln!(out, "{entry}")?;
+                }
+            }
+            writeln!(out, "{}", duel_outcome(&duel, &combatants))
+        }
+    }
+}
 
-    let mastery = WeaponMastery {
-        group: WeaponGroup::LargeSwords,
-        points: Default::default(),
-        base_threshold: 100.0,
+fn run_many(
+    out: &mut impl Write,
+    options: &CliOptions,
+    config: SimConfig,
+    critical_table: &CriticalTable,
+    arena: &Arena,
+    combatants: &[Vec<Combatant>; 2],
+) -> io::Result<()> {
+    let batch_config = BatchConfig {
+        max_seconds: options.max_seconds,
+        ..BatchConfig::new(options.runs, config.seed)
     };
+    let results = batch::run_battle_batch(config, critical_table, arena, combatants, &batch_config);
+    match options.format {
+        OutputFormat::Jsonl => {
+            for result in &results {
+                serde_json::to_writer(&mut *out, result)?;
+                writeln!(out)?;
+            }
+            Ok(())
+        }
+        OutputFormat::Json => write_report(out, config, batch_config, combatants, &results),
+        OutputFormat::Text => {
+            if options.verbosity >= 2 {
+                write_combatants(out, combatants)?;
+                for result in &results {
+                    writeln!(out, "{}", duel_outcome(result, combatants))?;
+                }
+            }
+            write_summary(
+                out,
+                &batch_config,
+                &batch::summarize(&results),
+                combatants,
+                options.verbosity,
+            )
+        }
+    }
+}
+
+fn write_report(
+    out: &mut impl Write,
+    config: SimConfig,
+    batch_config: BatchConfig,
+    combatants: &[Vec<Combatant>; 2],
+    results: &[DuelResult],
+) -> io::Result<()> {
+    let summary = batch::summarize(results);
+    let names = combatants.each_ref().map(|side| side_name(side));
+    export::write_batch_json(
+        out,
+        &export::BatchReport {
+            combatants: names.each_ref().map(String::as_str),
+            sim: config,
+            batch: batch_config,
+            summary: &summary,
+            duels: results,
+        },
+    )
+}
+
+/// The side's fighters by name; just the fighter's name in a duel.
+fn side_name(side: &[Combatant]) -> String {
+    side.iter()
+        .map(|combatant| combatant.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    let armor = character::ARMOR
+fn write_combatants(out: &mut impl Write, combatants: &[Vec<Combatant>; 2]) -> io::Result<()> {
+    let battle = combatants.iter().any(|side| side.len() > 1);
+    for (side, combatant) in combatants
         .iter()
-        .find(|a| a.name == "Chainmail" && a.region == ArmorRegion::Northern)
-        .cloned();
-
-    let equipment = Equipment {
-        weapon: Some(weapon.clone()),
-        shield: None,
-        armor,
-        weapon_material: character::MATERIALS
-            .iter()
-            .find(|m| m.kind == MaterialKind::Metal && m.name == "Steel")
-            .cloned(),
-        armor_material: None,
-        shield_material: None,
-    };
+        .enumerate()
+        .flat_map(|(side, fighters)| fighters.iter().map(move |combatant| (side, combatant)))
+    {
+        if battle {
+            write!(out, "[side {}] ", side + 1)?;
+        }
+        writeln!(out, "{}", combatant_sheet(combatant))?;
+    }
+    Ok(())
+}
 
-    let character = Character::builder("Example Duelist")
-        .level(
-            5,
-            Progression::new(
-                ProgressionTier::III,
-                ProgressionTier::III,
-                ProgressionTier::III,
-                ProgressionTier::III,
-            ),
-        )
-        .base_hp(10)
-        .abilities(abilities)
-        .weapon_mastery(mastery)
-        .equipment(equipment)
-        .build();
-
-    let derived = character.derived();
-
-    println!("Character: {}", character.name);
-    println!("Level: {} ({:?})", character.level, character.progression);
-    println!("Attack bonus: {}", derived.attack_bonus);
-    println!("Speed mod: {}", derived.speed_mod);
-    println!("Initiative mod: {}", derived.initiative_mod);
-    println!("Initiative die: {:?}", derived.initiative_die);
-    println!(
-        "Hit points: {} (x{:.1})",
-        derived.hit_points, derived.health_mult
+/// One line per fighter: the fixed stats, then only the options that differ from the
+/// defaults.
+fn combatant_sheet(combatant: &Combatant) -> String {
+    let mut line = format!(
+        "{}: {} (reach {:.1} ft, speed {}), HP {}",
+        combatant.name,
+        combatant.weapon_name,
+        combatant.reach_ft,
+        combatant.weapon_speed,
+        combatant.max_hp
     );
-    println!("Base DV: {}", derived.base_dv);
-    println!("Armor DR: {}", derived.armor_dr);
-    println!(
-        "Carry capacity (none/light/medium/heavy): {:?}",
-        derived.carry_capacity
-    );
-    println!("Load category: {}", derived.load_category);
+    if let Some(threshold) = combatant.threshold_of_pain {
+        line.push_str(&format!(" (ToP {threshold})"));
+    }
+    line.push_str(&format!(
+        ", attack {:+}, defense {:+}, DR {}",
+        combatant.attack_bonus, combatant.defense_mod, combatant.armor_dr
+    ));
+    if let Some(shield) = &combatant.shield_name {
+        line.push_str(&format!(", {shield}"));
+    }
+    match combatant.current_style() {
+        FightingStyle::WeaponAndShield
+        | FightingStyle::TwoHanded
+        | FightingStyle::OneHanded
+        | FightingStyle::OneHandedTwoHandGrip => {}
+        style => line.push_str(&format!(", {}", style.to_string().to_lowercase())),
+    }
+    line.push_str(&format!(
+        ", initiative d{}p{:+}",
+        combatant.initiative_die_sides, combatant.initiative_mod
+    ));
+    if combatant.stance != Stance::Normal {
+        line.push_str(&format!(
+            ", {}",
+            combatant.stance.to_string().to_lowercase()
+        ));
+    }
+    if combatant.charge {
+        line.push_str(", charges");
+    }
+    if combatant.set_for_charge {
+        line.push_str(", set vs charge");
+    }
+    if let Some(reserve) = &combatant.reserve_weapon {
+        line.push_str(&format!(", reserve {}", reserve.weapon_name));
+    }
+    if combatant.tactics.name() != "Standard" {
+        line.push_str(&format!(
+            ", {} tactics",
+            combatant.tactics.name().to_lowercase()
+        ));
+    }
+    if combatant.target_priority != TargetPriority::Nearest {
+        line.push_str(&format!(
+            ", targets {}",
+            combatant.target_priority.to_string().to_lowercase()
+        ));
+    }
+    line
+}
 
-    let mut sim = SimState::new(SimConfig::new(20.0, reach_ft));
-    let strength_damage = game_logic::strength_damage_for_weapon(
-        &weapon.name,
-        character.ability_mods.strength.damage,
-    );
-    let combatant = Combatant::new(
-        character.name.clone(),
-        character
-            .equipment
-            .weapon
-            .as_ref()
-            .map(|weapon| weapon.name.clone())
-            .unwrap_or_else(|| "Unarmed".to_string()),
-        derived.attack_bonus,
-        derived.base_dv,
-        derived.armor_dr,
-        character
-            .equipment
-            .armor
-            .as_ref()
-            .map(|armor| matches!(armor.armor_type, character::ArmorType::Heavy))
-            .unwrap_or(false),
-        character
-            .equipment
-            .weapon
-            .as_ref()
-            .map(|weapon| weapon.armor_pen)
-            .unwrap_or(0),
-        character
-            .equipment
-            .weapon
-            .as_ref()
-            .map(|weapon| weapon.damage_expr.clone())
-            .unwrap_or_else(|| "d4p".to_string()),
-        None,
-        strength_damage,
-        character
-            .equipment
-            .weapon
-            .as_ref()
-            .map(|weapon| weapon.speed)
-            .unwrap_or(10.0),
-        character
-            .equipment
-            .weapon
-            .as_ref()
-            .map(|weapon| weapon.reach_ft)
-            .unwrap_or(1.0),
-        5.0,
-        false,
-        false,
-        None,
-        character.equipment.weapon.is_some(),
-        character
-            .equipment
-            .weapon
-            .as_ref()
-            .map(|weapon| weapon.defense_bonus_always)
-            .unwrap_or(false),
-        derived.hit_points as i32,
-        None,
-        0,
-        0,
-        None,
-        false,
-        None,
-    );
-    sim.reset_with_combatants([combatant.clone(), combatant]);
-    println!("--- Simulation (1s ticks) ---");
-    while !sim.done {
-        sim.update(1.0);
-        println!(
-            "t={}s | distance={:.1} ft",
-            sim.elapsed_seconds,
-            sim.distance()
+fn duel_outcome(duel: &DuelResult, combatants: &[Vec<Combatant>; 2]) -> String {
+    let outcome = if duel.timed_out {
+        format!("timed out after {}s", duel.seconds)
+    } else if let Some(winner) = duel.winner {
+        let verb = if combatants[winner].len() > 1 {
+            "win"
+        } else {
+            "wins"
+        };
+        format!(
+            "{} {verb} at t={}s",
+            side_name(&combatants[winner]),
+            duel.seconds
+        )
+    } else {
+        format!("both down at t={}s", duel.seconds)
+    };
+    format!("Seed {}: {outcome}", duel.seed)
+}
+
+fn write_summary(
+    out: &mut impl Write,
+    batch_config: &BatchConfig,
+    summary: &BatchSummary,
+    combatants: &[Vec<Combatant>; 2],
+    verbosity: u8,
+) -> io::Result<()> {
+    let names = combatants.each_ref().map(|side| side_name(side));
+    writeln!(
+        out,
+        "{} vs {}: {} runs from seed {}",
+        names[0], names[1], summary.runs, batch_config.base_seed
+    )?;
+    for (side, name) in names.iter().enumerate() {
+        writeln!(out, "{name} wins: {:.1}%", summary.win_rate(side) * 100.0)?;
+    }
+    writeln!(
+        out,
+        "Draws: {:.1}%, timeouts: {:.1}%",
+        summary.draw_rate() * 100.0,
+        summary.timeout_rate() * 100.0
+    )?;
+    if verbosity == 0 {
+        return Ok(());
+    }
+    if let Some(ttk) = &summary.time_to_kill {
+        writeln!(
+            out,
+            "Time to kill: mean {:.1}s, median {}s, p10 {}s, p90 {}s, range {}-{}s",
+            ttk.mean, ttk.median, ttk.p10, ttk.p90, ttk.min, ttk.max
+        )?;
+    }
+    for (side, name) in names.iter().enumerate() {
+        writeln!(
+            out,
+            "{}: hit rate {:.1}%, {:.1} dmg/hit, shield broken in {:.1}% of duels",
+            name,
+            summary.hit_rate[side] * 100.0,
+            summary.avg_damage_per_hit[side],
+            summary.shield_break_rate[side] * 100.0
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_options_and_scenario_path() {
+        let options = parse_args(args(&[
+            "duel.json",
+            "-n",
+            "50",
+            "--seed",
+            "7",
+            "-f",
+            "jsonl",
+            "-q",
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(options.scenario, Some(PathBuf::from("duel.json")));
+        assert_eq!(options.runs, 50);
+        assert_eq!(options.seed, Some(7));
+        assert_eq!(options.format, OutputFormat::Jsonl);
+        assert_eq!(options.verbosity, 0);
+    }
+
+    #[test]
+    fn stacked_verbose_flags_count_each_v() {
+        let options = parse_args(args(&["-vv"])).unwrap().unwrap();
+        assert_eq!(options.verbosity, 3);
+        assert_eq!(
+            parse_args(args(&["-v", "-v"])).unwrap().unwrap().verbosity,
+            3
         );
-        if let Some(event) = &sim.last_event {
-            println!("{event}");
-        }
-        if sim.elapsed_seconds > 120 {
-            println!("Stopping after 120s (safety cutoff).");
-            break;
-        }
+        assert!(parse_args(args(&["-vx"])).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse_args(args(&["--runs"])).is_err());
+        assert!(parse_args(args(&["--runs", "0"])).is_err());
+        assert!(parse_args(args(&["--format", "xml"])).is_err());
+        assert!(parse_args(args(&["a.json", "b.json"])).is_err());
+        assert_eq!(parse_args(args(&["--help"])).unwrap(), None);
+    }
+
+    #[test]
+    fn quiet_batch_prints_only_rates() {
+        let catalogs = Catalogs::load().unwrap();
+        let scenario = scenario::parse_scenario(scenario::EXAMPLE_SCENARIO_JSON).unwrap();
+        let options = CliOptions {
+            runs: 20,
+            verbosity: 0,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        run_many(
+            &mut out,
+            &options,
+            scenario.sim_config(&catalogs).unwrap(),
+            &catalogs.critical_table,
+            &scenario.arena,
+            &scenario.combatants(&catalogs).unwrap(),
+        )
+        .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), 4);
+        assert!(text.starts_with("Example Duelist vs Shieldbearer: 20 runs from seed 1"));
     }
 }
@@ -1,27 +1,46 @@
-#[path = "../character.rs"]
-mod character;
-#[path = "../sim.rs"]
-mod sim;
-#[path = "../game_logic.rs"]
-mod game_logic;
-
-use character::ProgressionTier;
 use eframe::egui::{self, Color32, Pos2, Rect};
-use sim::{SimConfig, SimState};
-use game_logic::{
-    ArmorEntry, NpcPreset, PlayerConfig, ShieldEntry, WeaponHandedness, WeaponPreset, WeaponSize,
+use hackmaster_sim::arena::{Area, Point, TerrainKind};
+use hackmaster_sim::batch::{self, BatchConfig, BatchSummary, DuelResult};
+use hackmaster_sim::character::ProgressionTier;
+use hackmaster_sim::critical::{self, CRITICAL_EFFECTS_PATH, CriticalTable};
+use hackmaster_sim::export;
+use hackmaster_sim::fighting_style::FightingStyle;
+use hackmaster_sim::game_logic::{
+    self, ArmorEntry, NpcPreset, PlayerBuild, PlayerConfig, ShieldEntry, WeaponHandedness,
+    WeaponPreset, WeaponSize,
 };
+use hackmaster_sim::maneuver::{Retreat, Stance};
+use hackmaster_sim::sim::{self, SimConfig, SimState};
+use hackmaster_sim::tactics::TacticsKind;
+use std::path::{Path, PathBuf};
 
 struct SimGuiApp {
     running: bool,
     sim: SimState,
     players: [PlayerConfig; 2],
+    /// Display color per side; stays with the side when builds are swapped or loaded.
+    colors: [Color32; 2],
     weapon_catalog: Vec<WeaponPreset>,
     armor_catalog: Vec<ArmorEntry>,
     shield_catalog: Vec<ShieldEntry>,
     npc_presets: Vec<NpcPreset>,
     show_player_editor: [bool; 2],
     last_screen_size: egui::Vec2,
+    batch_runs: u32,
+    batch_summary: Option<BatchSummary>,
+    batch_results: Vec<DuelResult>,
+    batch_config: BatchConfig,
+    reseed_on_reset: bool,
+    export_status: Option<String>,
+    roster: Vec<(PathBuf, PlayerBuild)>,
+    roster_selection: [usize; 2],
+    roster_status: Option<String>,
+}
+
+enum RosterAction {
+    Save(usize),
+    Duplicate(usize),
+    Load(usize, usize),
 }
 
 impl SimGuiApp {
@@ -44,13 +63,25 @@ impl SimGuiApp {
                 Vec::new()
             }
         };
-        let sim = SimState::new(SimConfig::new(200.0, 1.0));
+        let critical_table = match critical::load_critical_table(CRITICAL_EFFECTS_PATH) {
+            Ok(table) => table,
+            Err(err) => {
+                eprintln!("Failed to load critical effects: {err}");
+                CriticalTable::default()
+            }
+        };
+        let sim = SimState::new(SimConfig::new(200.0, 1.0).with_seed(sim::random_seed()))
+            .with_critical_table(critical_table);
         let mut app = Self {
             running: false,
             sim,
             players: [
-                PlayerConfig::new("Fighter A", Color32::from_rgb(214, 93, 69), 1),
-                PlayerConfig::new("Fighter B", Color32::from_rgb(70, 140, 210), 2),
+                PlayerConfig::new("Fighter A", 1),
+                PlayerConfig::new("Fighter B", 2),
+            ],
+            colors: [
+                Color32::from_rgb(214, 93, 69),
+                Color32::from_rgb(70, 140, 210),
             ],
             weapon_catalog,
             armor_catalog,
@@ -58,12 +89,24 @@ impl SimGuiApp {
             npc_presets,
             show_player_editor: [false, false],
             last_screen_size: egui::vec2(0.0, 0.0),
+            batch_runs: 1000,
+            batch_summary: None,
+            batch_results: Vec::new(),
+            batch_config: BatchConfig::new(1000, 0),
+            reseed_on_reset: false,
+            export_status: None,
+            roster: game_logic::load_roster(Path::new(game_logic::ROSTER_DIR)),
+            roster_selection: [0, 0],
+            roster_status: None,
         };
         app.reset_positions();
         app
     }
 
     fn reset_positions(&mut self) {
+        if self.reseed_on_reset {
+            self.sim.config.seed = sim::random_seed();
+        }
         let combatants = game_logic::build_combatants(
             &self.players,
             &self.weapon_catalog,
@@ -74,6 +117,135 @@ impl SimGuiApp {
         self.sim.reset_with_combatants(combatants);
     }
 
+    fn load_build(&mut self, side: usize, path: &Path) -> Result<(), String> {
+        let build = game_logic::load_build(path)?;
+        self.apply_build(side, &build)
+    }
+
+    fn apply_build(&mut self, side: usize, build: &PlayerBuild) -> Result<(), String> {
+        build.apply_to(
+            &mut self.players[side],
+            &self.weapon_catalog,
+            &self.armor_catalog,
+            &self.shield_catalog,
+            &self.npc_presets,
+        )?;
+        self.reset_positions();
+        Ok(())
+    }
+
+    fn player_build(&self, side: usize) -> PlayerBuild {
+        PlayerBuild::from_player(
+            &self.players[side],
+            &self.weapon_catalog,
+            &self.armor_catalog,
+            &self.shield_catalog,
+            &self.npc_presets,
+        )
+    }
+
+    fn refresh_roster(&mut self) {
+        self.roster = game_logic::load_roster(Path::new(game_logic::ROSTER_DIR));
+    }
+
+    fn save_to_roster(&mut self, build: &PlayerBuild) {
+        let path = Path::new(game_logic::ROSTER_DIR).join(game_logic::build_file_name(&build.name));
+        self.roster_status = Some(match game_logic::save_build(&path, build) {
+            Ok(()) => format!("Saved {}", path.display()),
+            Err(err) => format!("Save failed: {err}"),
+        });
+        self.refresh_roster();
+    }
+
+    fn apply_roster_action(&mut self, action: RosterAction) {
+        match action {
+            RosterAction::Save(side) => {
+                let build = self.player_build(side);
+                self.save_to_roster(&build);
+            }
+            RosterAction::Duplicate(side) => {
+                let mut build = self.player_build(side);
+                build.name = self.unused_roster_name(&build.name);
+                self.save_to_roster(&build);
+                // Keep editing the copy so the original stays as saved.
+                self.players[side].name = build.name;
+                self.reset_positions();
+            }
+            RosterAction::Load(side, idx) => {
+                let Some((path, build)) = self.roster.get(idx).cloned() else {
+                    return;
+                };
+                self.roster_status = Some(match self.apply_build(side, &build) {
+                    Ok(()) => format!("Loaded {}", path.display()),
+                    Err(err) => format!("Load failed: {err}"),
+                });
+            }
+        }
+    }
+
+    fn unused_roster_name(&self, base: &str) -> String {
+        let taken = |name: &str| {
+            self.roster.iter().any(|(path, _)| {
+                path.file_name()
+                    .is_some_and(|file| file == game_logic::build_file_name(name).as_str())
+            })
+        };
+        (2..)
+            .map(|n| format!("{base} {n}"))
+            .find(|name| !taken(name))
+            .unwrap_or_else(|| base.to_string())
+    }
+
+    /// Trades builds between the sides; each side keeps its color.
+    fn swap_sides(&mut self) {
+        self.players.swap(0, 1);
+        self.show_player_editor.swap(0, 1);
+        self.reset_positions();
+    }
+
+    fn run_batch(&mut self) {
+        let combatants = game_logic::build_combatants(
+            &self.players,
+            &self.weapon_catalog,
+            &self.armor_catalog,
+            &self.shield_catalog,
+            &self.npc_presets,
+        );
+        self.batch_config = BatchConfig::new(self.batch_runs, self.sim.config.seed);
+        self.batch_results = batch::run_batch(
+            self.sim.config,
+            &self.sim.critical_table,
+            &combatants,
+            &self.batch_config,
+        );
+        self.batch_summary = Some(batch::summarize(&self.batch_results));
+    }
+
+    fn export_log(&mut self) {
+        let path = Path::new("sim_output").join(format!("duel_seed{}.jsonl", self.sim.seed()));
+        let result = export::export_log_jsonl(&path, &self.sim.combat_log);
+        self.export_status = Some(export_message(&path, result));
+    }
+
+    fn export_batch(&mut self) {
+        let Some(summary) = &self.batch_summary else {
+            return;
+        };
+        let path = Path::new("sim_output").join(format!(
+            "batch_seed{}_runs{}.json",
+            self.batch_config.base_seed, self.batch_config.runs
+        ));
+        let report = export::BatchReport {
+            combatants: [&self.sim.combatants[0].name, &self.sim.combatants[1].name],
+            sim: self.sim.config,
+            batch: self.batch_config,
+            summary,
+            duels: &self.batch_results,
+        };
+        let result = export::export_batch_json(&path, &report);
+        self.export_status = Some(export_message(&path, result));
+    }
+
     fn update_sim(&mut self, dt: f32) {
         if !self.running {
             return;
@@ -94,41 +266,69 @@ impl SimGuiApp {
         let hud_bottom = self.draw_hud(ui, rect, padding);
         self.draw_timeline(ui, rect, padding, hud_bottom + 25.0);
         let painter = ui.painter();
-        let ground_y = rect.center().y + rect.height() * 0.15;
-        let left = rect.left() + padding;
-        let right = rect.right() - padding;
-        let arena_width = (right - left).max(1.0);
-        let scale = arena_width / self.sim.config.start_distance.max(1.0);
-        if !scale.is_finite() {
+        let field = Rect::from_min_max(
+            Pos2::new(rect.left() + padding, hud_bottom + 45.0),
+            Pos2::new(rect.right() - padding, rect.bottom() - padding),
+        );
+        if field.width() <= 0.0 || field.height() <= 0.0 {
             return;
         }
+        // Seen from above: the starting field, grown to keep every fighter in view.
+        let margin = 5.0;
+        let mut min = Point::new(-margin, -margin);
+        let mut max = Point::new(self.sim.config.start_distance + margin, margin);
+        for actor in &self.sim.actors {
+            min = Point::new(
+                min.x.min(actor.position.x - margin),
+                min.y.min(actor.position.y - margin),
+            );
+            max = Point::new(
+                max.x.max(actor.position.x + margin),
+                max.y.max(actor.position.y + margin),
+            );
+        }
+        let scale = (field.width() / (max.x - min.x)).min(field.height() / (max.y - min.y));
+        if !scale.is_finite() || scale <= 0.0 {
+            return;
+        }
+        let center = (min + max) * 0.5;
+        let to_screen = |point: Point| {
+            field.center() + egui::vec2(point.x - center.x, point.y - center.y) * scale
+        };
+        painter.rect_stroke(field, 0.0, (1.0, Color32::from_gray(60)));
 
-        painter.line_segment(
-            [Pos2::new(left, ground_y), Pos2::new(right, ground_y)],
-            (2.0, Color32::from_gray(80)),
-        );
-
-        let mut x0 = left + self.sim.actors[0].position * scale;
-        let mut x1 = left + self.sim.actors[1].position * scale;
-        x0 = x0.clamp(left, right);
-        x1 = x1.clamp(left, right);
-        let gap = (x1 - x0).abs();
-        let min_gap = 28.0;
-        if gap < min_gap {
-            let dir = if x1 >= x0 { 1.0 } else { -1.0 };
-            if self.sim.combatants[0].reach_ft >= self.sim.combatants[1].reach_ft {
-                x1 = x0 + dir * min_gap;
-            } else {
-                x0 = x1 - dir * min_gap;
+        let terrain_painter = painter.with_clip_rect(field);
+        for feature in &self.sim.arena.terrain {
+            let fill = match feature.kind {
+                TerrainKind::Difficult { .. } => Color32::from_rgb(70, 60, 40),
+                TerrainKind::Wall => Color32::from_gray(120),
+                TerrainKind::Cover { .. } => Color32::from_rgb(90, 110, 70),
+            };
+            match feature.area {
+                Area::Rect { min, max } => {
+                    let area = Rect::from_two_pos(to_screen(min), to_screen(max));
+                    terrain_painter.rect_filled(area, 0.0, fill);
+                }
+                Area::Circle { center, radius } => {
+                    terrain_painter.circle_filled(to_screen(center), radius * scale, fill);
+                }
             }
         }
-
-        for (_idx, (x, player)) in [(x0, &self.players[0]), (x1, &self.players[1])]
-            .into_iter()
-            .enumerate()
-        {
-            let pos = Pos2::new(x, ground_y - 20.0);
-            painter.circle_filled(pos, 12.0, player.color);
+        for (actor, combatant) in self.sim.actors.iter().zip(&self.sim.combatants) {
+            let color = if combatant.hp > 0 {
+                self.colors[actor.side]
+            } else {
+                Color32::from_gray(90)
+            };
+            let pos = to_screen(actor.position);
+            painter.circle_stroke(
+                pos,
+                combatant.reach_ft.max(1.0) * scale,
+                (1.0, color.gamma_multiply(0.4)),
+            );
+            painter.circle_filled(pos, 8.0, color);
+            let facing = egui::vec2(actor.facing.x, actor.facing.y);
+            painter.line_segment([pos, pos + facing * 14.0], (2.0, Color32::from_gray(230)));
         }
     }
 
@@ -139,6 +339,7 @@ impl SimGuiApp {
         let y = rect.top() + padding * 0.5;
         let total_width = (right - left).max(1.0);
         let bar_height = 10.0;
+        let status_line_height = 14.0;
         let gap = 16.0;
         let bar_width = (total_width - gap).max(1.0) * 0.5;
 
@@ -159,7 +360,7 @@ impl SimGuiApp {
                 Pos2::new(fill_x, y),
                 egui::vec2(fill_width, bar_height),
             );
-            painter.rect_filled(fill_rect, 3.0, player.color);
+            painter.rect_filled(fill_rect, 3.0, self.colors[idx]);
             let name_x = if idx == 0 { bar_x } else { bar_x + bar_width };
             let align = if idx == 0 {
                 egui::Align2::LEFT_CENTER
@@ -173,8 +374,33 @@ impl SimGuiApp {
                 egui::TextStyle::Body.resolve(ui.style()),
                 Color32::from_gray(220),
             );
+            let now = self.sim.elapsed_seconds as f32;
+            let statuses = self.sim.combatants[idx]
+                .statuses
+                .iter()
+                .map(|status| {
+                    if status.until.is_finite() {
+                        format!("{} {:.0}s", status.kind, (status.until - now).max(0.0))
+                    } else {
+                        status.kind.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let status_align = if idx == 0 {
+                egui::Align2::LEFT_TOP
+            } else {
+                egui::Align2::RIGHT_TOP
+            };
+            painter.text(
+                Pos2::new(name_x, y + bar_height + 2.0),
+                status_align,
+                statuses,
+                egui::TextStyle::Small.resolve(ui.style()),
+                Color32::from_rgb(230, 180, 90),
+            );
         }
-        y + bar_height
+        y + bar_height + status_line_height
     }
 
     fn draw_timeline(&self, ui: &mut egui::Ui, rect: Rect, padding: f32, y: f32) {
@@ -200,12 +426,12 @@ impl SimGuiApp {
             );
         }
 
-        for (idx, player) in self.players.iter().enumerate() {
+        for (idx, color) in self.colors.iter().enumerate() {
             if let Some(next) = self.sim.combatants[idx].next_attack_time {
                 let t = (next - now).max(0.0).min(horizon);
                 let x = left + t * scale;
                 let pos = Pos2::new(x, y - 14.0);
-                painter.circle_filled(pos, 6.0, player.color);
+                painter.circle_filled(pos, 6.0, *color);
             }
         }
     }
@@ -256,6 +482,61 @@ impl eframe::App for SimGuiApp {
                         self.reset_positions();
                     }
                 }
+                ui.separator();
+                ui.label("Seed");
+                if ui
+                    .add(egui::DragValue::new(&mut self.sim.config.seed))
+                    .changed()
+                    && !self.running
+                {
+                    self.reset_positions();
+                }
+                if ui.button("New seed").clicked() {
+                    self.sim.config.seed = sim::random_seed();
+                    if !self.running {
+                        self.reset_positions();
+                    }
+                }
+                ui.checkbox(&mut self.reseed_on_reset, "Reseed on reset");
+                ui.separator();
+                let rules = &mut self.sim.config.rules;
+                let mut rules_changed = false;
+                ui.menu_button("Rules", |ui| {
+                    for (enabled, label) in [
+                        (&mut rules.reach_delay, "Reach delay"),
+                        (&mut rules.shield_breakage, "Shield breakage"),
+                        (&mut rules.threshold_of_pain, "Threshold of pain"),
+                        (&mut rules.knockback, "Knockback"),
+                        (&mut rules.criticals, "Critical hits"),
+                        (&mut rules.fumbles, "Fumbles"),
+                        (&mut rules.perfect_defense, "Perfect defense"),
+                        (&mut rules.hold_at_bay, "Hold at bay"),
+                        (&mut rules.flanking, "Flanking"),
+                    ] {
+                        rules_changed |= ui.checkbox(enabled, label).changed();
+                    }
+                });
+                if rules_changed && !self.running {
+                    self.reset_positions();
+                }
+                ui.separator();
+                ui.add(egui::DragValue::new(&mut self.batch_runs).range(1..=100_000));
+                if ui.button("Run batch").clicked() {
+                    self.run_batch();
+                }
+                ui.separator();
+                if ui.button("Export log").clicked() {
+                    self.export_log();
+                }
+                if ui
+                    .add_enabled(
+                        self.batch_summary.is_some(),
+                        egui::Button::new("Export batch"),
+                    )
+                    .clicked()
+                {
+                    self.export_batch();
+                }
             });
         });
 
@@ -285,9 +566,40 @@ impl eframe::App for SimGuiApp {
                         self.players[idx].move_speed
                     ));
                     if idx == 0 {
+                        if ui.button("Swap sides").clicked() {
+                            self.swap_sides();
+                        }
                         ui.separator();
                     }
                 }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.heading("Roster");
+                    if ui.button("Refresh").clicked() {
+                        self.refresh_roster();
+                    }
+                });
+                if self.roster.is_empty() {
+                    ui.label(format!("No saved builds in {}/", game_logic::ROSTER_DIR));
+                }
+                let mut action = None;
+                for (idx, (_, build)) in self.roster.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("A").clicked() {
+                            action = Some(RosterAction::Load(0, idx));
+                        }
+                        if ui.small_button("B").clicked() {
+                            action = Some(RosterAction::Load(1, idx));
+                        }
+                        ui.label(format!("{} ({})", build.name, build.weapon));
+                    });
+                }
+                if let Some(status) = &self.roster_status {
+                    ui.label(status);
+                }
+                if let Some(action) = action {
+                    self.apply_roster_action(action);
+                }
             });
 
         egui::SidePanel::right("status")
@@ -297,6 +609,7 @@ impl eframe::App for SimGuiApp {
                 ui.heading("Status");
                 ui.separator();
                 ui.label(format!("Elapsed: {}s", self.sim.elapsed_seconds));
+                ui.label(format!("Seed: {}", self.sim.config.seed));
                 ui.label(format!("Distance: {:.1} ft", self.sim.distance()));
                 ui.label(format!(
                     "Stop distance: {:.1} ft",
@@ -311,9 +624,9 @@ impl eframe::App for SimGuiApp {
                     "{} HP: {}",
                     self.sim.combatants[1].name, self.sim.combatants[1].hp
                 ));
-                if let Some(event) = &self.sim.last_event {
+                if let Some(entry) = self.sim.last_entry() {
                     ui.separator();
-                    ui.label(event);
+                    ui.label(entry.to_string());
                 }
                 ui.label(if self.sim.done {
                     "State: Done"
@@ -333,13 +646,20 @@ impl eframe::App for SimGuiApp {
                     self.players[1].name,
                     self.weapon_catalog[self.players[1].weapon_index].name
                 ));
+                if let Some(summary) = &self.batch_summary {
+                    ui.separator();
+                    render_batch_summary(ui, summary, &self.sim.combatants);
+                }
+                if let Some(status) = &self.export_status {
+                    ui.label(status);
+                }
                 ui.separator();
                 ui.label("Combat log");
                 egui::ScrollArea::vertical()
                     .max_height(180.0)
                     .show(ui, |ui| {
-                        for line in &self.sim.combat_log {
-                            ui.label(line);
+                        for entry in &self.sim.combat_log {
+                            ui.label(entry.to_string());
                         }
                     });
             });
@@ -350,6 +670,7 @@ impl eframe::App for SimGuiApp {
             self.draw_arena(ui, response.rect);
         });
 
+        let mut roster_action = None;
         for idx in 0..self.players.len() {
             let name = self.players[idx].name.clone();
             let mut open = self.show_player_editor[idx];
@@ -360,6 +681,39 @@ impl eframe::App for SimGuiApp {
                 .resizable(true)
                 .show(ctx, |ui| {
                     let id_prefix = if idx == 0 { "p1" } else { "p2" };
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            roster_action = Some(RosterAction::Save(idx));
+                        }
+                        if ui.button("Duplicate").clicked() {
+                            roster_action = Some(RosterAction::Duplicate(idx));
+                        }
+                        ui.separator();
+                        let selection = &mut self.roster_selection[idx];
+                        *selection = (*selection).min(self.roster.len().saturating_sub(1));
+                        egui::ComboBox::from_id_source(format!("{id_prefix}_roster"))
+                            .selected_text(
+                                self.roster
+                                    .get(*selection)
+                                    .map_or("No saved builds", |(_, build)| build.name.as_str()),
+                            )
+                            .show_ui(ui, |ui| {
+                                for (entry, (_, build)) in self.roster.iter().enumerate() {
+                                    ui.selectable_value(selection, entry, build.name.as_str());
+                                }
+                            });
+                        if ui
+                            .add_enabled(!self.roster.is_empty(), egui::Button::new("Load"))
+                            .clicked()
+                        {
+                            roster_action = Some(RosterAction::Load(idx, *selection));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Color");
+                        ui.color_edit_button_srgba(&mut self.colors[idx]);
+                    });
+                    ui.separator();
                     let (player, opponent) = if idx == 0 {
                         let (left, right) = self.players.split_at_mut(1);
                         (&mut left[0], &right[0])
@@ -380,6 +734,9 @@ impl eframe::App for SimGuiApp {
                 });
             self.show_player_editor[idx] = open;
         }
+        if let Some(action) = roster_action {
+            self.apply_roster_action(action);
+        }
 
         if self.running {
             ctx.request_repaint();
@@ -454,10 +811,6 @@ fn render_player_editor(
             ui.add(egui::Slider::new(&mut player.base_hp, 1..=200).step_by(1.0));
         });
     });
-    ui.horizontal(|ui| {
-        ui.label("Color");
-        ui.color_edit_button_srgba(&mut player.color);
-    });
     ui.horizontal(|ui| {
         ui.label("Move speed (ft/s)");
         ui.add(egui::Slider::new(&mut player.move_speed, 0.0..=40.0).step_by(5.0));
@@ -596,6 +949,127 @@ fn render_player_editor(
     if player.two_hand_grip && can_two_hand {
         ui.label("Two-hand grip: +3 damage, +2 speed");
     }
+    ui.horizontal(|ui| {
+        ui.label("Reserve weapon");
+        let reserve_label = player
+            .reserve_weapon_index
+            .and_then(|idx| weapon_catalog.get(idx))
+            .map_or("None", |weapon| weapon.name.as_str());
+        egui::ComboBox::from_id_source(format!("{id_prefix}_reserve_weapon"))
+            .selected_text(reserve_label)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut player.reserve_weapon_index, None, "None");
+                for (idx, weapon) in weapon_catalog.iter().enumerate() {
+                    ui.selectable_value(
+                        &mut player.reserve_weapon_index,
+                        Some(idx),
+                        weapon.name.as_str(),
+                    );
+                }
+            });
+    });
+    egui::ComboBox::from_label("Fighting style")
+        .selected_text(
+            player
+                .fighting_style
+                .map_or("From gear".to_string(), |style| style.to_string()),
+        )
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut player.fighting_style, None, "From gear");
+            for style in FightingStyle::ALL {
+                ui.selectable_value(&mut player.fighting_style, Some(style), style.to_string());
+            }
+        });
+    if player
+        .fighting_style
+        .is_some_and(FightingStyle::uses_two_weapons)
+    {
+        ui.horizontal(|ui| {
+            ui.label("Off-hand weapon");
+            let off_hand_label = player
+                .off_hand_weapon_index
+                .and_then(|idx| weapon_catalog.get(idx))
+                .map_or("None", |weapon| weapon.name.as_str());
+            egui::ComboBox::from_id_source(format!("{id_prefix}_off_hand_weapon"))
+                .selected_text(off_hand_label)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut player.off_hand_weapon_index, None, "None");
+                    for (idx, weapon) in weapon_catalog.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut player.off_hand_weapon_index,
+                            Some(idx),
+                            weapon.name.as_str(),
+                        );
+                    }
+                });
+        });
+    }
+    if player.fighting_style == Some(FightingStyle::TwoShields) {
+        ui.horizontal(|ui| {
+            ui.label("Second shield");
+            egui::ComboBox::from_id_source(format!("{id_prefix}_second_shield"))
+                .selected_text(shield_display_name(
+                    player
+                        .second_shield_index
+                        .and_then(|idx| shield_catalog.get(idx)),
+                ))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut player.second_shield_index, None, "None");
+                    for (idx, shield) in shield_catalog.iter().enumerate().skip(1) {
+                        ui.selectable_value(
+                            &mut player.second_shield_index,
+                            Some(idx),
+                            shield.label.clone(),
+                        );
+                    }
+                });
+        });
+    }
+    egui::ComboBox::from_label("Tactics")
+        .selected_text(player.tactics.to_string())
+        .show_ui(ui, |ui| {
+            for tactics in TacticsKind::ALL {
+                ui.selectable_value(&mut player.tactics, tactics, tactics.to_string());
+            }
+        });
+    if !player.tactics_rules.is_empty() {
+        ui.horizontal(|ui| {
+            ui.label("Scripted rules, checked first:");
+            if ui.button("Clear").clicked() {
+                player.tactics_rules.clear();
+            }
+        });
+        for rule in &player.tactics_rules {
+            ui.label(format!("  {rule}"));
+        }
+    }
+    egui::ComboBox::from_label("Stance")
+        .selected_text(player.stance.to_string())
+        .show_ui(ui, |ui| {
+            for stance in Stance::ALL {
+                ui.selectable_value(&mut player.stance, stance, stance.to_string());
+            }
+        });
+    let retreat_label = player
+        .retreat
+        .map_or("Stand fast".to_string(), |retreat| retreat.to_string());
+    egui::ComboBox::from_label("When attacked")
+        .selected_text(retreat_label)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut player.retreat, None, "Stand fast");
+            for retreat in Retreat::ALL {
+                ui.selectable_value(&mut player.retreat, Some(retreat), retreat.to_string());
+            }
+        });
+    if !weapon.set_for_charge {
+        player.set_for_charge = false;
+    }
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut player.charge, "Charge");
+        ui.add_enabled_ui(weapon.set_for_charge, |ui| {
+            ui.checkbox(&mut player.set_for_charge, "Set vs charge");
+        });
+    });
     ui.horizontal(|ui| {
         let enabled = can_two_hand && !is_two_handed;
         ui.add_enabled_ui(enabled, |ui| {
@@ -690,6 +1164,7 @@ fn render_player_editor(
             "Hit points: {} (x{:.1})",
             derived.hit_points, derived.health_mult
         ));
+        ui.label(format!("Threshold of pain: {}", derived.threshold_of_pain));
         ui.label(format!("Attack bonus: {}", derived.attack_bonus));
         ui.label(format!("Speed mod: {}", derived.speed_mod));
         ui.label(format!("Initiative mod: {}", derived.initiative_mod));
@@ -765,6 +1240,42 @@ fn render_player_editor(
     }
 }
 
+fn export_message(path: &Path, result: std::io::Result<()>) -> String {
+    match result {
+        Ok(()) => format!("Exported {}", path.display()),
+        Err(err) => format!("Export to {} failed: {err}", path.display()),
+    }
+}
+
+fn render_batch_summary(ui: &mut egui::Ui, summary: &BatchSummary, combatants: &[sim::Combatant]) {
+    ui.label(format!("Batch ({} runs)", summary.runs));
+    for (side, combatant) in combatants.iter().enumerate() {
+        ui.label(format!(
+            "{}: win {:.1}% | hit {:.1}% | dmg/hit {:.1} | shield broken {:.1}%",
+            combatant.name,
+            summary.win_rate(side) * 100.0,
+            summary.hit_rate[side] * 100.0,
+            summary.avg_damage_per_hit[side],
+            summary.shield_break_rate[side] * 100.0
+        ));
+    }
+    ui.label(format!(
+        "Draw {:.1}% | Timeout {:.1}%",
+        summary.draw_rate() * 100.0,
+        summary.timeout_rate() * 100.0
+    ));
+    if let Some(ttk) = &summary.time_to_kill {
+        ui.label(format!(
+            "Time to kill: mean {:.1}s | median {:.0}s | p10-p90 {:.0}-{:.0}s",
+            ttk.mean, ttk.median, ttk.p10, ttk.p90
+        ));
+        ui.label(format!(
+            "Time to kill range: {:.0}-{:.0}s (p25-p75 {:.0}-{:.0}s)",
+            ttk.min, ttk.max, ttk.p25, ttk.p75
+        ));
+    }
+}
+
 fn ability_percentile_editor(
     ui: &mut egui::Ui,
     id: &str,
@@ -852,6 +1363,15 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "HackMaster Simulator",
         options,
-        Box::new(|_cc| Ok(Box::new(SimGuiApp::new()))),
+        Box::new(|_cc| {
+            let mut app = SimGuiApp::new();
+            // `sim_gui [BUILD_A] [BUILD_B]` starts with saved builds on either side.
+            for (side, path) in std::env::args().skip(1).take(2).enumerate() {
+                if let Err(err) = app.load_build(side, Path::new(&path)) {
+                    eprintln!("Failed to load build: {err}");
+                }
+            }
+            Ok(Box::new(app))
+        }),
     )
 }
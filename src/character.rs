@@ -1,8 +1,9 @@
 #![allow(dead_code)]
 
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ProgressionTier {
     I,
     II,
@@ -37,7 +38,7 @@ impl ProgressionTier {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Progression {
     pub attack: ProgressionTier,
     pub speed: ProgressionTier,
@@ -87,6 +88,19 @@ impl Default for InitiativeDieQuality {
     }
 }
 
+impl InitiativeDieQuality {
+    /// Each step better than standard drops the initiative die one size (d12 -> d4).
+    pub fn die_sides(self) -> i32 {
+        match self {
+            InitiativeDieQuality::Standard => 12,
+            InitiativeDieQuality::OneBetter => 10,
+            InitiativeDieQuality::TwoBetter => 8,
+            InitiativeDieQuality::ThreeBetter => 6,
+            InitiativeDieQuality::FourBetter => 4,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct AbilityScore {
     pub base: u8,
@@ -245,6 +259,13 @@ pub enum WeaponGroup {
     Shields,
 }
 
+impl WeaponGroup {
+    /// Polearms and spears are the weapons that can be braced against a charge.
+    pub fn can_set_for_charge(self) -> bool {
+        matches!(self, WeaponGroup::Polearms | WeaponGroup::Spears)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum MasteryAspect {
     Attack,
@@ -330,6 +351,8 @@ pub struct DerivedStats {
     pub initiative_die: InitiativeDieQuality,
     pub health_mult: f32,
     pub hit_points: u32,
+    /// Damage from a single blow past this forces a trauma save: 40% of hit points.
+    pub threshold_of_pain: u32,
     pub base_dv: i32,
     pub armor_dr: i32,
     pub load_category: &'static str,
@@ -367,6 +390,7 @@ impl Character {
         let health_mult = health_mult_for(self.level, self.progression.health);
         let hp_from_con = self.abilities.constitution as f32 * health_mult;
         let hit_points = (self.base_hp as f32 + hp_from_con).round() as u32;
+        let threshold_of_pain = (hit_points * 2 / 5).max(1);
 
         let armor_dr = self
             .equipment
@@ -407,6 +431,7 @@ impl Character {
             initiative_die,
             health_mult,
             hit_points,
+            threshold_of_pain,
             base_dv,
             armor_dr,
             load_category,
@@ -1139,6 +1164,7 @@ mod tests {
             .build();
         let derived = character.derived();
         assert_eq!(derived.hit_points, 24);
+        assert_eq!(derived.threshold_of_pain, 9);
     }
 
     #[test]
@@ -1191,6 +1217,13 @@ mod tests {
         assert_eq!(initiative_mod_for(20, ProgressionTier::V), -4);
     }
 
+    #[test]
+    fn initiative_die_quality_shrinks_die() {
+        assert_eq!(InitiativeDieQuality::Standard.die_sides(), 12);
+        assert_eq!(initiative_die_for(5, ProgressionTier::V).die_sides(), 8);
+        assert_eq!(initiative_die_for(20, ProgressionTier::V).die_sides(), 4);
+    }
+
     #[test]
     fn health_advancement_matches_table() {
         let low = health_mult_for(1, ProgressionTier::I);
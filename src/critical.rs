@@ -0,0 +1,167 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::status::StatusKind;
+
+const EMBEDDED_CRITICAL_EFFECTS_JSON: &str = include_str!("../data/critical_effects.json");
+pub const CRITICAL_EFFECTS_PATH: &str = "data/critical_effects.json";
+
+/// One band of the critical hit table, chosen by how far the attack beat the defense.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CriticalEffect {
+    pub min_severity: i32,
+    pub name: String,
+    /// Extra damage dice; this damage ignores DR.
+    pub extra_damage: String,
+    #[serde(default)]
+    pub status: Option<StatusKind>,
+    #[serde(default)]
+    pub status_seconds: f32,
+}
+
+/// One band of the fumble table, chosen by a d20 roll.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct FumbleEffect {
+    pub max_roll: i32,
+    pub name: String,
+    /// Seconds added to the fumbler's next attack.
+    #[serde(default)]
+    pub delay_seconds: f32,
+    #[serde(default)]
+    pub status: Option<StatusKind>,
+    #[serde(default)]
+    pub status_seconds: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CriticalTable {
+    pub criticals: Vec<CriticalEffect>,
+    pub fumbles: Vec<FumbleEffect>,
+}
+
+impl Default for CriticalTable {
+    fn default() -> Self {
+        serde_json::from_str(EMBEDDED_CRITICAL_EFFECTS_JSON)
+            .expect("embedded critical effects table is valid")
+    }
+}
+
+impl CriticalTable {
+    /// The most severe band the margin reaches, if any.
+    pub fn critical_for(&self, severity: i32) -> Option<&CriticalEffect> {
+        self.criticals
+            .iter()
+            .filter(|effect| severity >= effect.min_severity)
+            .max_by_key(|effect| effect.min_severity)
+    }
+
+    /// The first band covering the roll; rolls past the table use the last band.
+    pub fn fumble_for(&self, roll: i32) -> Option<&FumbleEffect> {
+        self.fumbles
+            .iter()
+            .filter(|effect| roll <= effect.max_roll)
+            .min_by_key(|effect| effect.max_roll)
+            .or_else(|| self.fumbles.iter().max_by_key(|effect| effect.max_roll))
+    }
+}
+
+pub fn load_critical_table(path: &str) -> Result<CriticalTable, String> {
+    let data =
+        fs::read_to_string(path).unwrap_or_else(|_| EMBEDDED_CRITICAL_EFFECTS_JSON.to_string());
+    serde_json::from_str(&data).map_err(|err| format!("{path}: {err}"))
+}
+
+/// Whether the defense die came up a natural 20 or 19 on a d20 defense.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefenseResult {
+    /// Natural 20: the attack is turned aside completely.
+    Perfect,
+    /// Natural 19: a hit still lands, but for half damage.
+    NearPerfect,
+}
+
+impl DefenseResult {
+    pub fn from_die(sides: i32, die: i32) -> Option<Self> {
+        if sides != 20 {
+            return None;
+        }
+        // A penetrating d20 only totals 20 or more when the first roll was a 20.
+        match die {
+            20.. => Some(DefenseResult::Perfect),
+            19 => Some(DefenseResult::NearPerfect),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DefenseResult::Perfect => "perfect defense",
+            DefenseResult::NearPerfect => "near-perfect defense",
+        }
+    }
+}
+
+/// A critical hit as resolved, for the combat log.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CriticalHit {
+    /// Attack total minus defense total.
+    pub severity: i32,
+    pub name: String,
+    pub detail: String,
+    pub extra_damage: i32,
+    pub status: Option<StatusKind>,
+    pub status_seconds: f32,
+}
+
+/// A fumble as resolved, for the combat log.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Fumble {
+    pub roll: i32,
+    pub name: String,
+    pub delay_seconds: f32,
+    pub status: Option<StatusKind>,
+    pub status_seconds: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bands_pick_the_highest_reached_severity_and_first_covering_roll() {
+        let table = CriticalTable::default();
+        assert_eq!(table.critical_for(0).unwrap().min_severity, 0);
+        assert_eq!(table.critical_for(7).unwrap().min_severity, 5);
+        assert_eq!(table.critical_for(99).unwrap().min_severity, 20);
+        assert!(table.critical_for(-1).is_none());
+        assert_eq!(table.fumble_for(1).unwrap().max_roll, 10);
+        assert_eq!(table.fumble_for(17).unwrap().max_roll, 19);
+        assert_eq!(table.fumble_for(25).unwrap().max_roll, 20);
+        assert_eq!(load_critical_table(CRITICAL_EFFECTS_PATH).unwrap(), table);
+    }
+
+    #[test]
+    fn malformed_table_file_is_reported() {
+        let path = std::env::temp_dir().join(format!("hm_criticals_{}.json", std::process::id()));
+        fs::write(&path, "{\"criticals\": [").unwrap();
+        let err = load_critical_table(path.to_str().unwrap()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(err.starts_with(path.to_str().unwrap()));
+    }
+
+    #[test]
+    fn only_d20_defense_dice_can_be_perfect() {
+        assert_eq!(
+            DefenseResult::from_die(20, 27),
+            Some(DefenseResult::Perfect)
+        );
+        assert_eq!(
+            DefenseResult::from_die(20, 19),
+            Some(DefenseResult::NearPerfect)
+        );
+        assert_eq!(DefenseResult::from_die(20, 18), None);
+        assert_eq!(DefenseResult::from_die(12, 12), None);
+    }
+}
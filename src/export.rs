@@ -0,0 +1,131 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::batch::{BatchConfig, BatchSummary, DuelResult};
+use crate::sim::{CombatEvent, LogEntry, SimConfig};
+
+/// One line of a duel trace: an event tagged with the second it happened on.
+#[derive(Serialize)]
+struct TraceLine<'a> {
+    time: u32,
+    #[serde(flatten)]
+    event: &'a CombatEvent,
+}
+
+/// Everything needed to reproduce and compare a batch run.
+#[derive(Serialize)]
+pub struct BatchReport<'a> {
+    pub combatants: [&'a str; 2],
+    pub sim: SimConfig,
+    pub batch: BatchConfig,
+    pub summary: &'a BatchSummary,
+    pub duels: &'a [DuelResult],
+}
+
+/// Writes one JSON object per combat event, in log order.
+pub fn write_log_jsonl(mut writer: impl Write, log: &[LogEntry]) -> io::Result<()> {
+    for entry in log {
+        for event in &entry.events {
+            serde_json::to_writer(
+                &mut writer,
+                &TraceLine {
+                    time: entry.time,
+                    event,
+                },
+            )?;
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()
+}
+
+pub fn write_batch_json(mut writer: impl Write, report: &BatchReport) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, report)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+pub fn export_log_jsonl(path: &Path, log: &[LogEntry]) -> io::Result<()> {
+    write_log_jsonl(BufWriter::new(create_file(path)?), log)
+}
+
+pub fn export_batch_json(path: &Path, report: &BatchReport) -> io::Result<()> {
+    write_batch_json(BufWriter::new(create_file(path)?), report)
+}
+
+fn create_file(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    File::create(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch;
+    use crate::sim::{Combatant, SimState};
+
+    fn duelist(name: &str) -> Combatant {
+        Combatant {
+            name: name.to_string(),
+            weapon_name: "Test Blade".to_string(),
+            damage_expr: "2d6p".to_string(),
+            has_weapon: true,
+            max_hp: 20,
+            hp: 20,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn log_jsonl_has_one_tagged_line_per_event() {
+        let mut sim = SimState::new(SimConfig::new(10.0, 1.0).with_seed(3));
+        sim.reset_with_combatants([duelist("A"), duelist("B")]);
+        batch::run_duel(&mut sim, 600);
+        let mut out = Vec::new();
+        write_log_jsonl(&mut out, &sim.combat_log).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let events: usize = sim.combat_log.iter().map(|entry| entry.events.len()).sum();
+        assert_eq!(lines.len(), events);
+        assert_eq!(lines[0]["type"], "seed");
+        assert_eq!(lines[0]["seed"], 3);
+        assert_eq!(lines[0]["time"], 0);
+        assert_eq!(lines.last().unwrap()["type"], "defeat");
+        assert!(lines.iter().any(|line| line["roll"]["attack_die"].is_i64()));
+    }
+
+    #[test]
+    fn batch_report_round_trips_as_json() {
+        let config = SimConfig::new(10.0, 1.0);
+        let batch_config = BatchConfig::new(4, 9);
+        let results = batch::run_batch(
+            config,
+            &Default::default(),
+            &[duelist("A"), duelist("B")],
+            &batch_config,
+        );
+        let summary = batch::summarize(&results);
+        let report = BatchReport {
+            combatants: ["A", "B"],
+            sim: config,
+            batch: batch_config,
+            summary: &summary,
+            duels: &results,
+        };
+        let mut out = Vec::new();
+        write_batch_json(&mut out, &report).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value["batch"]["runs"], 4);
+        assert_eq!(value["summary"]["runs"], 4);
+        assert_eq!(value["duels"].as_array().unwrap().len(), 4);
+        assert_eq!(value["duels"][1]["seed"], 10);
+    }
+}
@@ -0,0 +1,178 @@
+//! How a fighter holds their weapons and shields (references/fighting_styles.md).
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Seconds added to each attack after the first with both weapons in the offensive
+/// two-weapon style.
+pub const TWO_WEAPON_SPEED_PENALTY: f32 = 2.0;
+/// The off-hand weapon's first attack comes this long after the first primary attack,
+/// plus half the primary weapon's speed.
+pub const OFF_HAND_DELAY_SECONDS: f32 = 2.0;
+/// Damage taken off every off-hand blow.
+pub const OFF_HAND_DAMAGE_PENALTY: i32 = 2;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FightingStyle {
+    /// d20p plus the shield bonus against attacks the shield covers.
+    WeaponAndShield,
+    /// Full d20p against one attack between the fighter's own, d20p-4 after that.
+    TwoHanded,
+    /// d20p-4, but attacks one second faster for focusing on a single weapon.
+    #[default]
+    OneHanded,
+    /// A one-handed weapon held in both hands: +3 damage and +2 speed, defending as
+    /// two-handed.
+    OneHandedTwoHandGrip,
+    /// Attacks with the off-hand weapon as well, both more slowly; defends on a d10p.
+    TwoWeaponsOffensive,
+    /// Parries with the off-hand weapon, adding its defense bonus, and defends as
+    /// two-handed between primary attacks.
+    TwoWeaponsDefensive,
+    /// Fights unarmed or bashes behind the shield: +1 defense, but opponents attack at
+    /// half their weapon speed.
+    ShieldOnly,
+    /// Shield bashes only. Both shields guard the front and flanks, and opponents attack
+    /// at half their weapon speed.
+    TwoShields,
+}
+
+impl FightingStyle {
+    pub const ALL: [FightingStyle; 8] = [
+        FightingStyle::WeaponAndShield,
+        FightingStyle::TwoHanded,
+        FightingStyle::OneHanded,
+        FightingStyle::OneHandedTwoHandGrip,
+        FightingStyle::TwoWeaponsOffensive,
+        FightingStyle::TwoWeaponsDefensive,
+        FightingStyle::ShieldOnly,
+        FightingStyle::TwoShields,
+    ];
+
+    /// The style the gear in hand implies when none is chosen: a shield, a weapon in both
+    /// hands, or one weapon alone.
+    pub fn for_hands(shield: bool, two_hand_grip: bool, one_handed_weapon: bool) -> Self {
+        match (shield, two_hand_grip, one_handed_weapon) {
+            (true, _, _) => FightingStyle::WeaponAndShield,
+            (false, true, true) => FightingStyle::OneHandedTwoHandGrip,
+            (false, true, false) => FightingStyle::TwoHanded,
+            (false, false, _) => FightingStyle::OneHanded,
+        }
+    }
+
+    pub fn uses_shield(self) -> bool {
+        matches!(
+            self,
+            FightingStyle::WeaponAndShield | FightingStyle::ShieldOnly | FightingStyle::TwoShields
+        )
+    }
+
+    pub fn uses_two_weapons(self) -> bool {
+        matches!(
+            self,
+            FightingStyle::TwoWeaponsOffensive | FightingStyle::TwoWeaponsDefensive
+        )
+    }
+
+    /// Seconds added to the weapon speed between attacks.
+    pub fn speed_mod(self) -> f32 {
+        match self {
+            FightingStyle::OneHanded => -1.0,
+            FightingStyle::TwoWeaponsOffensive => TWO_WEAPON_SPEED_PENALTY,
+            _ => 0.0,
+        }
+    }
+
+    /// Sides of the melee defense die where the fighter would otherwise roll a d20p.
+    pub fn defense_die_sides(self) -> i32 {
+        match self {
+            FightingStyle::TwoWeaponsOffensive => 10,
+            _ => 20,
+        }
+    }
+
+    /// Whether the weapons alone defend on the full die rather than at -4. Styles that
+    /// guard one attack per window only do so while that defense is still unspent.
+    pub fn full_weapon_defense(self, window_open: bool) -> bool {
+        match self {
+            FightingStyle::TwoWeaponsOffensive => true,
+            _ if self.defends_once_per_window() => window_open,
+            _ => false,
+        }
+    }
+
+    /// Full defense against the first attack between the fighter's own attacks only.
+    pub fn defends_once_per_window(self) -> bool {
+        matches!(
+            self,
+            FightingStyle::TwoHanded
+                | FightingStyle::OneHandedTwoHandGrip
+                | FightingStyle::TwoWeaponsDefensive
+        )
+    }
+
+    /// Defense added on top of the shield bonus.
+    pub fn defense_bonus(self) -> i32 {
+        match self {
+            FightingStyle::ShieldOnly => 1,
+            _ => 0,
+        }
+    }
+
+    /// With nothing to fear from a weapon, opponents attack at half their weapon speed.
+    pub fn halves_opponent_speed(self) -> bool {
+        matches!(self, FightingStyle::ShieldOnly | FightingStyle::TwoShields)
+    }
+}
+
+impl fmt::Display for FightingStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            FightingStyle::WeaponAndShield => "Weapon and shield",
+            FightingStyle::TwoHanded => "Two-handed",
+            FightingStyle::OneHanded => "One-handed",
+            FightingStyle::OneHandedTwoHandGrip => "One-handed, two-hand grip",
+            FightingStyle::TwoWeaponsOffensive => "Two weapons (offensive)",
+            FightingStyle::TwoWeaponsDefensive => "Two weapons (defensive)",
+            FightingStyle::ShieldOnly => "Shield only",
+            FightingStyle::TwoShields => "Two shields",
+        };
+        write!(f, "{label}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gear_in_hand_implies_a_style() {
+        assert_eq!(
+            FightingStyle::for_hands(true, false, true),
+            FightingStyle::WeaponAndShield
+        );
+        assert_eq!(
+            FightingStyle::for_hands(false, true, true),
+            FightingStyle::OneHandedTwoHandGrip
+        );
+        assert_eq!(
+            FightingStyle::for_hands(false, true, false),
+            FightingStyle::TwoHanded
+        );
+        assert_eq!(
+            FightingStyle::for_hands(false, false, true),
+            FightingStyle::OneHanded
+        );
+    }
+
+    #[test]
+    fn window_styles_defend_fully_only_once() {
+        assert!(FightingStyle::TwoHanded.full_weapon_defense(true));
+        assert!(!FightingStyle::TwoHanded.full_weapon_defense(false));
+        assert!(FightingStyle::TwoWeaponsOffensive.full_weapon_defense(false));
+        assert!(!FightingStyle::OneHanded.full_weapon_defense(true));
+        assert_eq!(FightingStyle::TwoWeaponsOffensive.defense_die_sides(), 10);
+    }
+}
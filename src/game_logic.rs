@@ -2,10 +2,15 @@ use crate::character::{
     AbilityScore, AbilitySet, Armor, Character, DerivedStats, Equipment, Progression, Shield,
     Weapon, WeaponGroup, WeaponMastery,
 };
-use crate::sim::{self, Combatant};
-use eframe::egui::Color32;
-use serde::Deserialize;
+use crate::fighting_style::FightingStyle;
+use crate::maneuver::{Retreat, Stance};
+use crate::sim::{self, Combatant, WeaponLoadout};
+use crate::tactic_rules::{ScriptedTactics, TacticRule};
+use crate::tactics::{Tactics, TacticsKind, TargetPriority};
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::path::Path;
+use std::sync::Arc;
 
 const EMBEDDED_WEAPONS_JSON: &str = include_str!("../data/weapons.json");
 const EMBEDDED_ARMOR_JSON: &str = include_str!("../data/armor.json");
@@ -27,6 +32,8 @@ pub struct WeaponPreset {
     pub reach_ft: f32,
     pub armor_pen: i32,
     pub defense_bonus_always: bool,
+    /// Can be braced to receive a charge (Ready Against Charge).
+    pub set_for_charge: bool,
     pub size: WeaponSize,
     pub handedness: WeaponHandedness,
     pub ammunition: Option<String>,
@@ -86,7 +93,6 @@ struct NpcPresetsFile {
 #[derive(Clone)]
 pub struct PlayerConfig {
     pub name: String,
-    pub color: Color32,
     pub level: u8,
     pub progression: Progression,
     pub base_hp: u32,
@@ -110,13 +116,24 @@ pub struct PlayerConfig {
     pub npc_preset: Option<usize>,
     pub two_hand_grip: bool,
     pub use_jab: bool,
+    pub reserve_weapon_index: Option<usize>,
+    /// `None` takes the style the weapon and shield imply.
+    pub fighting_style: Option<FightingStyle>,
+    pub off_hand_weapon_index: Option<usize>,
+    pub second_shield_index: Option<usize>,
+    pub stance: Stance,
+    pub retreat: Option<Retreat>,
+    pub charge: bool,
+    pub set_for_charge: bool,
+    pub tactics: TacticsKind,
+    pub tactics_rules: Vec<TacticRule>,
+    pub target_priority: TargetPriority,
 }
 
 impl PlayerConfig {
-    pub fn new(name: &str, color: Color32, weapon_index: usize) -> Self {
+    pub fn new(name: &str, weapon_index: usize) -> Self {
         Self {
             name: name.to_string(),
-            color,
             level: 1,
             progression: Progression::default(),
             base_hp: 10,
@@ -140,8 +157,411 @@ impl PlayerConfig {
             npc_preset: None,
             two_hand_grip: false,
             use_jab: false,
+            reserve_weapon_index: None,
+            fighting_style: None,
+            off_hand_weapon_index: None,
+            second_shield_index: None,
+            stance: Stance::Normal,
+            retreat: None,
+            charge: false,
+            set_for_charge: false,
+            tactics: TacticsKind::Standard,
+            tactics_rules: Vec::new(),
+            target_priority: TargetPriority::Nearest,
         }
     }
+
+    /// The chosen tactics, with any scripted rules taking precedence over them.
+    pub fn tactics(&self) -> Arc<dyn Tactics> {
+        let tactics = self.tactics.tactics();
+        if self.tactics_rules.is_empty() {
+            tactics
+        } else {
+            Arc::new(ScriptedTactics::new(self.tactics_rules.clone(), tactics))
+        }
+    }
+}
+
+/// A saved `PlayerConfig`, referencing catalog entries by name so it survives catalog
+/// reordering. Missing fields fall back to the `PlayerConfig::new` defaults.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerBuild {
+    pub name: String,
+    pub level: u8,
+    pub progression: Progression,
+    pub base_hp: u32,
+    pub move_speed: f32,
+    pub strength_base: u8,
+    pub strength_pct: u8,
+    pub dex_base: u8,
+    pub dex_pct: u8,
+    pub intelligence: u8,
+    pub wisdom: u8,
+    pub constitution: u8,
+    pub looks: u8,
+    pub charisma: u8,
+    pub weapon: String,
+    /// Armor catalog label, e.g. `Chainmail (Northern)`; `None` for no armor.
+    pub armor: Option<String>,
+    pub shield: Option<String>,
+    pub weapon_material_tier: i32,
+    pub armor_material_tier: i32,
+    pub projectile_material_tier: i32,
+    pub shield_material_tier: i32,
+    pub npc_preset: Option<String>,
+    pub two_hand_grip: bool,
+    pub use_jab: bool,
+    /// Weapon catalog name of a second weapon carried for a weapon swap.
+    pub reserve_weapon: Option<String>,
+    /// How the weapons and shields are held; implied by them when omitted.
+    pub fighting_style: Option<FightingStyle>,
+    /// Weapon catalog name of the second weapon for the two-weapon styles.
+    pub off_hand_weapon: Option<String>,
+    /// Shield catalog label of the second shield for the two-shield style.
+    pub second_shield: Option<String>,
+    pub stance: Stance,
+    pub retreat: Option<Retreat>,
+    pub charge: bool,
+    pub set_for_charge: bool,
+    pub tactics: TacticsKind,
+    /// Ordered rules such as `if hp < 30% then fight defensively 4`, checked before
+    /// `tactics`; see `tactic_rules`.
+    pub tactics_rules: Vec<TacticRule>,
+    /// Which enemy to go after in a battle.
+    pub target_priority: TargetPriority,
+}
+
+impl Default for PlayerBuild {
+    fn default() -> Self {
+        let player = PlayerConfig::new("Fighter", 0);
+        Self {
+            name: player.name,
+            level: player.level,
+            progression: player.progression,
+            base_hp: player.base_hp,
+            move_speed: player.move_speed,
+            strength_base: player.strength_base,
+            strength_pct: player.strength_pct,
+            dex_base: player.dex_base,
+            dex_pct: player.dex_pct,
+            intelligence: player.intelligence,
+            wisdom: player.wisdom,
+            constitution: player.constitution,
+            looks: player.looks,
+            charisma: player.charisma,
+            weapon: "Fist".to_string(),
+            armor: None,
+            shield: None,
+            weapon_material_tier: player.weapon_material_tier,
+            armor_material_tier: player.armor_material_tier,
+            projectile_material_tier: player.projectile_material_tier,
+            shield_material_tier: player.shield_material_tier,
+            npc_preset: None,
+            two_hand_grip: player.two_hand_grip,
+            use_jab: player.use_jab,
+            reserve_weapon: None,
+            fighting_style: player.fighting_style,
+            off_hand_weapon: None,
+            second_shield: None,
+            stance: player.stance,
+            retreat: player.retreat,
+            charge: player.charge,
+            set_for_charge: player.set_for_charge,
+            tactics: player.tactics,
+            tactics_rules: player.tactics_rules.clone(),
+            target_priority: player.target_priority,
+        }
+    }
+}
+
+impl PlayerBuild {
+    pub fn from_player(
+        player: &PlayerConfig,
+        weapon_catalog: &[WeaponPreset],
+        armor_catalog: &[ArmorEntry],
+        shield_catalog: &[ShieldEntry],
+        npc_presets: &[NpcPreset],
+    ) -> Self {
+        Self {
+            name: player.name.clone(),
+            level: player.level,
+            progression: player.progression,
+            base_hp: player.base_hp,
+            move_speed: player.move_speed,
+            strength_base: player.strength_base,
+            strength_pct: player.strength_pct,
+            dex_base: player.dex_base,
+            dex_pct: player.dex_pct,
+            intelligence: player.intelligence,
+            wisdom: player.wisdom,
+            constitution: player.constitution,
+            looks: player.looks,
+            charisma: player.charisma,
+            weapon: weapon_catalog
+                .get(player.weapon_index)
+                .map(|weapon| weapon.name.clone())
+                .unwrap_or_else(|| "Fist".to_string()),
+            armor: armor_catalog
+                .get(player.armor_index)
+                .filter(|entry| entry.armor.is_some())
+                .map(|entry| entry.label.clone()),
+            shield: shield_catalog
+                .get(player.shield_index)
+                .filter(|entry| entry.shield.is_some())
+                .map(|entry| entry.label.clone()),
+            weapon_material_tier: player.weapon_material_tier,
+            armor_material_tier: player.armor_material_tier,
+            projectile_material_tier: player.projectile_material_tier,
+            shield_material_tier: player.shield_material_tier,
+            npc_preset: player
+                .npc_preset
+                .and_then(|idx| npc_presets.get(idx))
+                .map(|preset| preset.name.clone()),
+            two_hand_grip: player.two_hand_grip,
+            use_jab: player.use_jab,
+            reserve_weapon: player
+                .reserve_weapon_index
+                .and_then(|idx| weapon_catalog.get(idx))
+                .map(|weapon| weapon.name.clone()),
+            fighting_style: player.fighting_style,
+            off_hand_weapon: player
+                .off_hand_weapon_index
+                .and_then(|idx| weapon_catalog.get(idx))
+                .map(|weapon| weapon.name.clone()),
+            second_shield: player
+                .second_shield_index
+                .and_then(|idx| shield_catalog.get(idx))
+                .filter(|entry| entry.shield.is_some())
+                .map(|entry| entry.label.clone()),
+            stance: player.stance,
+            retreat: player.retreat,
+            charge: player.charge,
+            set_for_charge: player.set_for_charge,
+            tactics: player.tactics,
+            tactics_rules: player.tactics_rules.clone(),
+            target_priority: player.target_priority,
+        }
+    }
+
+    pub fn to_player(
+        &self,
+        weapon_catalog: &[WeaponPreset],
+        armor_catalog: &[ArmorEntry],
+        shield_catalog: &[ShieldEntry],
+        npc_presets: &[NpcPreset],
+    ) -> Result<PlayerConfig, String> {
+        let mut player = PlayerConfig::new(&self.name, 0);
+        self.apply_to(
+            &mut player,
+            weapon_catalog,
+            armor_catalog,
+            shield_catalog,
+            npc_presets,
+        )?;
+        Ok(player)
+    }
+
+    /// Overwrites `player` with this build; it is untouched if any name fails to resolve.
+    pub fn apply_to(
+        &self,
+        player: &mut PlayerConfig,
+        weapon_catalog: &[WeaponPreset],
+        armor_catalog: &[ArmorEntry],
+        shield_catalog: &[ShieldEntry],
+        npc_presets: &[NpcPreset],
+    ) -> Result<(), String> {
+        let weapon_index = weapon_catalog
+            .iter()
+            .position(|weapon| weapon.name == self.weapon)
+            .ok_or_else(|| format!("{}: unknown weapon {:?}", self.name, self.weapon))?;
+        let armor_index = match &self.armor {
+            Some(label) => armor_catalog
+                .iter()
+                .position(|entry| &entry.label == label)
+                .ok_or_else(|| format!("{}: unknown armor {label:?}", self.name))?,
+            None => 0,
+        };
+        let shield_index = match &self.shield {
+            Some(label) => shield_catalog
+                .iter()
+                .position(|entry| &entry.label == label)
+                .ok_or_else(|| format!("{}: unknown shield {label:?}", self.name))?,
+            None => 0,
+        };
+        let reserve_weapon_index = match &self.reserve_weapon {
+            Some(name) => Some(
+                weapon_catalog
+                    .iter()
+                    .position(|weapon| &weapon.name == name)
+                    .ok_or_else(|| format!("{}: unknown reserve weapon {name:?}", self.name))?,
+            ),
+            None => None,
+        };
+        let off_hand_weapon_index = match &self.off_hand_weapon {
+            Some(name) => Some(
+                weapon_catalog
+                    .iter()
+                    .position(|weapon| &weapon.name == name)
+                    .ok_or_else(|| format!("{}: unknown off-hand weapon {name:?}", self.name))?,
+            ),
+            None => None,
+        };
+        let second_shield_index = match &self.second_shield {
+            Some(label) => Some(
+                shield_catalog
+                    .iter()
+                    .position(|entry| &entry.label == label)
+                    .ok_or_else(|| format!("{}: unknown second shield {label:?}", self.name))?,
+            ),
+            None => None,
+        };
+        if let Some(style) = self.fighting_style {
+            check_fighting_style(
+                style,
+                &weapon_catalog[weapon_index],
+                shield_index > 0,
+                off_hand_weapon_index.map(|idx| &weapon_catalog[idx]),
+                second_shield_index.is_some(),
+            )
+            .map_err(|err| format!("{}: {err}", self.name))?;
+        }
+        let npc_preset = match &self.npc_preset {
+            Some(name) => Some(
+                npc_presets
+                    .iter()
+                    .position(|preset| &preset.name == name)
+                    .ok_or_else(|| format!("{}: unknown NPC preset {name:?}", self.name))?,
+            ),
+            None => None,
+        };
+        player.name = self.name.clone();
+        player.level = self.level;
+        player.progression = self.progression;
+        player.base_hp = self.base_hp;
+        player.move_speed = self.move_speed;
+        player.strength_base = self.strength_base;
+        player.strength_pct = self.strength_pct;
+        player.dex_base = self.dex_base;
+        player.dex_pct = self.dex_pct;
+        player.intelligence = self.intelligence;
+        player.wisdom = self.wisdom;
+        player.constitution = self.constitution;
+        player.looks = self.looks;
+        player.charisma = self.charisma;
+        player.weapon_index = weapon_index;
+        player.armor_index = armor_index;
+        player.weapon_material_tier = self.weapon_material_tier;
+        player.armor_material_tier = self.armor_material_tier;
+        player.projectile_material_tier = self.projectile_material_tier;
+        player.shield_index = shield_index;
+        player.shield_material_tier = self.shield_material_tier;
+        player.npc_preset = npc_preset;
+        player.two_hand_grip =
+            self.two_hand_grip || self.fighting_style == Some(FightingStyle::OneHandedTwoHandGrip);
+        player.use_jab = self.use_jab;
+        player.reserve_weapon_index = reserve_weapon_index;
+        player.fighting_style = self.fighting_style;
+        player.off_hand_weapon_index = off_hand_weapon_index;
+        player.second_shield_index = second_shield_index;
+        player.stance = self.stance;
+        player.retreat = self.retreat;
+        player.charge = self.charge;
+        player.set_for_charge = self.set_for_charge;
+        player.tactics = self.tactics;
+        player.tactics_rules = self.tactics_rules.clone();
+        player.target_priority = self.target_priority;
+        sanitize_projectile_tier(player, &weapon_catalog[weapon_index]);
+        Ok(())
+    }
+}
+
+/// Whether the gear fits the chosen style, per references/fighting_styles.md.
+fn check_fighting_style(
+    style: FightingStyle,
+    weapon: &WeaponPreset,
+    shield: bool,
+    off_hand: Option<&WeaponPreset>,
+    second_shield: bool,
+) -> Result<(), String> {
+    let one_handed = |weapon: &WeaponPreset| weapon.handedness == WeaponHandedness::OneHanded;
+    let unarmed = matches!(weapon.group, WeaponGroup::Unarmed | WeaponGroup::Shields);
+    let problem = match style {
+        _ if style.uses_shield() && !shield => Some("needs a shield"),
+        FightingStyle::TwoShields if !second_shield => Some("needs a second shield"),
+        FightingStyle::ShieldOnly | FightingStyle::TwoShields if !unarmed => {
+            Some("fights unarmed or with shield bashes only")
+        }
+        FightingStyle::WeaponAndShield | FightingStyle::OneHanded if !one_handed(weapon) => {
+            Some("needs a one-handed weapon")
+        }
+        FightingStyle::TwoHanded if one_handed(weapon) => Some("needs a two-handed weapon"),
+        FightingStyle::OneHandedTwoHandGrip
+            if !one_handed(weapon) || weapon.size == WeaponSize::Small =>
+        {
+            Some("needs a one-handed weapon big enough for both hands")
+        }
+        _ if style.uses_two_weapons() && off_hand.is_none() => Some("needs an off-hand weapon"),
+        _ if style.uses_two_weapons()
+            && (shield || !one_handed(weapon) || !off_hand.is_some_and(one_handed)) =>
+        {
+            Some("needs two one-handed weapons and no shield")
+        }
+        FightingStyle::OneHanded if shield => Some("cannot carry a shield"),
+        _ => None,
+    };
+    match problem {
+        Some(problem) => Err(format!("{style} style {problem}")),
+        None => Ok(()),
+    }
+}
+
+pub fn load_build(path: &Path) -> Result<PlayerBuild, String> {
+    let data = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    serde_json::from_str(&data).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+pub fn save_build(path: &Path, build: &PlayerBuild) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("{}: {err}", parent.display()))?;
+    }
+    let data = serde_json::to_string_pretty(build).map_err(|err| err.to_string())?;
+    fs::write(path, data + "\n").map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// Directory the GUI keeps its roster of saved builds in.
+pub const ROSTER_DIR: &str = "builds";
+
+/// File name for a build in the roster, e.g. `Sir Roland` -> `sir_roland.json`.
+pub fn build_file_name(name: &str) -> String {
+    let mut slug = String::new();
+    for ch in name.trim().chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+    let slug = slug.trim_matches('_');
+    if slug.is_empty() {
+        "build.json".to_string()
+    } else {
+        format!("{slug}.json")
+    }
+}
+
+/// Every readable build in `dir`, sorted by name. A missing directory is an empty roster.
+pub fn load_roster(dir: &Path) -> Vec<(std::path::PathBuf, PlayerBuild)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut roster: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| load_build(&path).ok().map(|build| (path, build)))
+        .collect();
+    roster.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    roster
 }
 
 pub fn weapon_uses_projectiles(weapon: &WeaponPreset) -> bool {
@@ -372,16 +792,22 @@ pub fn build_combatant(
     let can_two_hand = weapon_preset.handedness == WeaponHandedness::OneHanded
         && (weapon_preset.size == WeaponSize::Medium || weapon_preset.size == WeaponSize::Large);
     let effective_two_hand = is_two_handed || (player.two_hand_grip && can_two_hand);
-    let two_hand_damage_bonus = if effective_two_hand && can_two_hand { 3 } else { 0 };
-    let two_hand_speed_bonus = if effective_two_hand && can_two_hand { 2.0 } else { 0.0 };
+    let two_hand_damage_bonus = if effective_two_hand && can_two_hand {
+        sim::TWO_HAND_GRIP_DAMAGE
+    } else {
+        0
+    };
+    let two_hand_speed_bonus = if effective_two_hand && can_two_hand {
+        sim::TWO_HAND_GRIP_SPEED
+    } else {
+        0.0
+    };
     let use_jab = player.use_jab && weapon_preset.jab_speed.is_some();
     let min_speed = min_weapon_speed_for_size(weapon_preset.size);
     let jab_speed = (weapon_preset.jab_speed.unwrap_or(weapon_speed) + speed_mod).max(min_speed);
-    let jab_special_expr = if use_jab {
-        weapon_preset.jab_special_expr.clone()
-    } else {
-        None
-    };
+    let jab_special_expr = weapon_preset
+        .jab_speed
+        .and_then(|_| weapon_preset.jab_special_expr.clone());
 
     let mut name = character.name;
     let is_ranged_weapon = is_ranged_weapon(&weapon_name);
@@ -401,7 +827,10 @@ pub fn build_combatant(
         character.ability_mods.strength.damage,
     ) + two_hand_damage_bonus
         + material_damage_bonus;
+    let reserve_strength_damage = character.ability_mods.strength.damage;
+    let mut dex_defense = character.ability_mods.dexterity.defense;
     let mut max_hp = derived.hit_points as i32;
+    let mut threshold_of_pain = derived.threshold_of_pain as i32;
     let mut shield_name = shield_data.map(|shield| shield.name.to_string());
     let mut shield_defense_bonus = shield_data.map(|shield| shield.defense_bonus).unwrap_or(0);
     let mut shield_dr = shield_data.map(|shield| shield.dr).unwrap_or(0);
@@ -409,19 +838,30 @@ pub fn build_combatant(
     let mut shield_intact = shield_name.is_some();
     let mut shield_breakage =
         shield_data.map(|shield| breakage_steps_from_thresholds(shield.breakage_thresholds));
+    let off_hand = player
+        .off_hand_weapon_index
+        .and_then(|idx| weapon_catalog.get(idx));
+    let mut second_shield_defense_bonus = player
+        .second_shield_index
+        .and_then(|idx| shield_catalog.get(idx))
+        .and_then(|entry| entry.shield.as_ref())
+        .map_or(0, |shield| shield.defense_bonus);
     if let Some(preset) = player.npc_preset.and_then(|idx| npc_presets.get(idx)) {
         name = preset.name.clone();
         attack_bonus = preset.attack_bonus;
         defense_mod = preset.defense_mod;
         armor_dr = preset.armor_dr;
         strength_damage = preset.damage_bonus;
+        dex_defense = 0;
         max_hp = preset.hp.max(1);
+        threshold_of_pain = preset.top;
         shield_name = None;
         shield_defense_bonus = 0;
         shield_dr = 0;
         shield_cover_value = None;
         shield_intact = false;
         shield_breakage = None;
+        second_shield_defense_bonus = 0;
     }
 
     Combatant::new(
@@ -455,22 +895,97 @@ pub fn build_combatant(
         shield_intact,
         shield_breakage,
     )
+    .with_initiative(derived.initiative_die.die_sides(), derived.initiative_mod)
+    .with_threshold_of_pain(threshold_of_pain, player.constitution as i32)
+    .with_stance(player.stance)
+    .with_retreat(player.retreat)
+    .with_charge(
+        player.charge,
+        player.set_for_charge && weapon_preset.set_for_charge,
+        dex_defense,
+    )
+    .with_jab_speed(weapon_preset.jab_speed.map(|_| jab_speed))
+    .with_draw_seconds(draw_seconds_for_size(weapon_preset.size))
+    .with_can_two_hand(can_two_hand && has_weapon)
+    .with_reserve_weapon(
+        player
+            .reserve_weapon_index
+            .and_then(|idx| weapon_catalog.get(idx))
+            .map(|preset| loadout(player, preset, speed_mod, reserve_strength_damage)),
+    )
+    .with_fighting_style(player.fighting_style.unwrap_or(FightingStyle::for_hands(
+        shield_intact,
+        effective_two_hand,
+        can_two_hand && has_weapon,
+    )))
+    .with_off_hand(
+        off_hand.map(|preset| loadout(player, preset, speed_mod, reserve_strength_damage)),
+        // Fighters are all man-sized, so only small weapons are smaller than the wielder.
+        off_hand.is_some_and(|preset| preset.size == WeaponSize::Small),
+    )
+    .with_second_shield(second_shield_defense_bonus)
+    .with_tactics(player.tactics())
+    .with_target_priority(player.target_priority)
 }
 
-pub fn stop_distance_for_players(players: &[PlayerConfig; 2], weapon_catalog: &[WeaponPreset]) -> f32 {
-    let reach_a = weapon_catalog
-        .get(players[0].weapon_index)
-        .map(|weapon| {
-            sim::max_range_for_weapon(&weapon.name).unwrap_or_else(|| weapon.reach_ft.max(1.0))
-        })
-        .unwrap_or(1.0);
-    let reach_b = weapon_catalog
-        .get(players[1].weapon_index)
-        .map(|weapon| {
-            sim::max_range_for_weapon(&weapon.name).unwrap_or_else(|| weapon.reach_ft.max(1.0))
+/// Seconds to draw a weapon of this size in the middle of a fight.
+pub fn draw_seconds_for_size(size: WeaponSize) -> f32 {
+    match size {
+        WeaponSize::Small => 0.0,
+        WeaponSize::Medium => 1.0,
+        WeaponSize::Large => 2.0,
+    }
+}
+
+/// A weapon carried besides the one in hand, gripped two-handed only if it needs both
+/// hands.
+fn loadout(
+    player: &PlayerConfig,
+    preset: &WeaponPreset,
+    speed_mod: f32,
+    strength_damage: i32,
+) -> WeaponLoadout {
+    let min_speed = min_weapon_speed_for_size(preset.size);
+    WeaponLoadout {
+        weapon_name: preset.name.clone(),
+        damage_expr: preset.damage_expr.clone(),
+        shield_damage_expr: preset
+            .shield_damage_expr
+            .clone()
+            .filter(|expr| expr != "-" && !expr.is_empty()),
+        strength_damage: strength_damage_for_weapon(&preset.name, strength_damage),
+        armor_penetration: preset.armor_pen,
+        weapon_speed: (preset.speed + speed_mod).max(min_speed),
+        reach_ft: preset.reach_ft,
+        two_hand_grip: preset.handedness == WeaponHandedness::TwoHanded,
+        use_jab: false,
+        jab_speed: preset
+            .jab_speed
+            .map(|jab_speed| (jab_speed + speed_mod).max(min_speed)),
+        jab_special_expr: preset
+            .jab_speed
+            .and_then(|_| preset.jab_special_expr.clone()),
+        has_weapon: true,
+        weapon_defense_always: preset.defense_bonus_always,
+        set_for_charge: player.set_for_charge && preset.set_for_charge,
+        draw_seconds: draw_seconds_for_size(preset.size),
+    }
+}
+
+/// The longest reach (or missile range) among the fighters, where they engage.
+pub fn stop_distance_for_players(players: &[PlayerConfig], weapon_catalog: &[WeaponPreset]) -> f32 {
+    players
+        .iter()
+        .map(|player| {
+            weapon_catalog
+                .get(player.weapon_index)
+                .map(|weapon| {
+                    sim::max_range_for_weapon(&weapon.name)
+                        .unwrap_or_else(|| weapon.reach_ft.max(1.0))
+                })
+                .unwrap_or(1.0)
         })
-        .unwrap_or(1.0);
-    reach_a.max(reach_b)
+        .fold(1.0, f32::max)
 }
 
 pub fn default_weapon_catalog() -> Vec<WeaponPreset> {
@@ -1014,6 +1529,7 @@ struct WeaponJson {
     ammunition: Option<String>,
     armor_penetration: Option<i32>,
     defense_bonus_always: Option<bool>,
+    set_for_charge: Option<bool>,
     #[serde(rename = "reach_or_range")]
     reach_or_range: Option<String>,
     size: String,
@@ -1111,6 +1627,7 @@ fn load_weapon_catalog(path: &str) -> Result<Vec<WeaponPreset>, String> {
             reach_ft,
             armor_pen: entry.armor_penetration.unwrap_or(0),
             defense_bonus_always: entry.defense_bonus_always.unwrap_or(false),
+            set_for_charge: entry.set_for_charge.unwrap_or(false) && group.can_set_for_charge(),
             size,
             handedness,
             ammunition: entry.ammunition.clone(),
@@ -1384,6 +1901,7 @@ fn weapon_preset(
         reach_ft,
         armor_pen: 0,
         defense_bonus_always: false,
+        set_for_charge: group.can_set_for_charge(),
         size: WeaponSize::Medium,
         handedness: WeaponHandedness::OneHanded,
         ammunition: None,
@@ -1460,6 +1978,113 @@ mod tests {
     use super::*;
     use crate::character;
 
+    #[test]
+    fn player_build_round_trips_by_name() {
+        let (weapons, armor, shields) = load_catalogs().unwrap();
+        let mut player = PlayerConfig::new("Duelist", 3);
+        player.level = 4;
+        player.strength_base = 16;
+        player.armor_index = armor.len() - 1;
+        player.shield_index = shields.len() - 1;
+        let build = PlayerBuild::from_player(&player, &weapons, &armor, &shields, &[]);
+        assert_eq!(build.weapon, weapons[3].name);
+        let json = serde_json::to_string(&build).unwrap();
+        let parsed: PlayerBuild = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, build);
+
+        // Reordering the catalog must not change which weapon the build resolves to.
+        let mut reordered = weapons.clone();
+        reordered.reverse();
+        let restored = parsed.to_player(&reordered, &armor, &shields, &[]).unwrap();
+        assert_eq!(reordered[restored.weapon_index].name, weapons[3].name);
+        assert_eq!(restored.armor_index, player.armor_index);
+        assert_eq!(restored.shield_index, player.shield_index);
+        assert_eq!(restored.level, 4);
+        assert_eq!(restored.strength_base, 16);
+    }
+
+    #[test]
+    fn roster_file_names_are_slugs() {
+        assert_eq!(build_file_name("Sir Roland"), "sir_roland.json");
+        assert_eq!(build_file_name("  Orc #2 (elite) "), "orc_2_elite.json");
+        assert_eq!(build_file_name("???"), "build.json");
+    }
+
+    #[test]
+    fn roster_lists_saved_builds_by_name() {
+        let dir = std::env::temp_dir().join(format!("hm_roster_{}", std::process::id()));
+        for name in ["Zed", "Abe"] {
+            let build = PlayerBuild {
+                name: name.to_string(),
+                ..Default::default()
+            };
+            save_build(&dir.join(build_file_name(name)), &build).unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "not a build").unwrap();
+        let roster = load_roster(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let names: Vec<&str> = roster.iter().map(|(_, build)| build.name.as_str()).collect();
+        assert_eq!(names, ["Abe", "Zed"]);
+        assert!(load_roster(&dir).is_empty());
+    }
+
+    #[test]
+    fn player_build_rejects_unknown_names_without_touching_player() {
+        let build = PlayerBuild {
+            weapon: "Lightsaber".to_string(),
+            ..Default::default()
+        };
+        let mut player = PlayerConfig::new("Keep", 0);
+        let err = build
+            .apply_to(
+                &mut player,
+                &default_weapon_catalog(),
+                &default_armor_catalog(),
+                &default_shield_catalog(),
+                &[],
+            )
+            .unwrap_err();
+        assert!(err.contains("Lightsaber"));
+        assert_eq!(player.name, "Keep");
+    }
+
+    #[test]
+    fn fighting_style_must_suit_the_gear() {
+        let weapons = default_weapon_catalog();
+        let mut build = PlayerBuild {
+            weapon: "Short sword".to_string(),
+            shield: None,
+            fighting_style: Some(FightingStyle::TwoWeaponsOffensive),
+            ..Default::default()
+        };
+        let mut player = PlayerConfig::new("Twin", 0);
+        let apply = |build: &PlayerBuild, player: &mut PlayerConfig| {
+            build.apply_to(
+                player,
+                &weapons,
+                &default_armor_catalog(),
+                &default_shield_catalog(),
+                &[],
+            )
+        };
+        assert_eq!(
+            apply(&build, &mut player).unwrap_err(),
+            "Fighter: Two weapons (offensive) style needs an off-hand weapon"
+        );
+        build.off_hand_weapon = Some("Dagger".to_string());
+        apply(&build, &mut player).unwrap();
+        assert_eq!(
+            player.fighting_style,
+            Some(FightingStyle::TwoWeaponsOffensive)
+        );
+        assert_eq!(
+            player
+                .off_hand_weapon_index
+                .map(|idx| weapons[idx].name.as_str()),
+            Some("Dagger")
+        );
+    }
+
     #[test]
     fn material_bonuses_melee_use_weapon_tier() {
         let (attack, damage) = material_bonuses(2, 4, false, false);
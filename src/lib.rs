@@ -0,0 +1,31 @@
+//! HackMaster 5e combat engine: character building, the second-by-second duel
+//! simulation, and the batch/export/scenario tooling built on top of it.
+//!
+//! The usual path is `PlayerConfig` (or a saved `PlayerBuild`) through
+//! [`build_combatant`] into a [`Combatant`], then a [`SimState`] driven with
+//! [`SimState::tick`] or a whole [`run_batch`].
+
+pub mod arena;
+pub mod batch;
+pub mod character;
+pub mod critical;
+pub mod export;
+pub mod fighting_style;
+pub mod game_logic;
+pub mod maneuver;
+pub mod scenario;
+pub mod sim;
+pub mod status;
+pub mod tactic_rules;
+pub mod tactics;
+
+pub use batch::{BatchConfig, BatchSummary, DuelResult, run_batch, run_duel, summarize};
+pub use character::{Character, DerivedStats};
+pub use fighting_style::FightingStyle;
+pub use game_logic::{PlayerBuild, PlayerConfig, build_combatant, build_combatants};
+pub use maneuver::Stance;
+pub use scenario::{Catalogs, Scenario};
+pub use sim::{CombatEvent, Combatant, LogEntry, RuleToggles, SimConfig, SimState};
+pub use status::{StatusEffect, StatusKind};
+pub use tactic_rules::{ScriptedTactics, TacticRule};
+pub use tactics::{Tactics, TacticsKind};
@@ -0,0 +1,161 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// How a fighter commits to each attack (references/special_combat_moves.md).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stance {
+    #[default]
+    Normal,
+    /// +5 to the attack, -2 to defense until the next attack.
+    AggressiveAttack,
+    /// Trade 2 attack per point of defense, from 1 to 4 points; hits cannot penetrate.
+    FightDefensively(u8),
+}
+
+impl Stance {
+    pub const ALL: [Stance; 6] = [
+        Stance::Normal,
+        Stance::AggressiveAttack,
+        Stance::FightDefensively(1),
+        Stance::FightDefensively(2),
+        Stance::FightDefensively(3),
+        Stance::FightDefensively(4),
+    ];
+
+    pub fn attack_mod(self) -> i32 {
+        match self {
+            Stance::Normal => 0,
+            Stance::AggressiveAttack => 5,
+            Stance::FightDefensively(level) => -2 * i32::from(level.clamp(1, 4)),
+        }
+    }
+
+    /// Applies from the attack the stance was declared with until the fighter's next attack.
+    pub fn defense_mod(self) -> i32 {
+        match self {
+            Stance::Normal => 0,
+            Stance::AggressiveAttack => -2,
+            Stance::FightDefensively(level) => i32::from(level.clamp(1, 4)),
+        }
+    }
+
+    pub fn allows_penetration(self) -> bool {
+        !matches!(self, Stance::FightDefensively(_))
+    }
+
+    /// Attack modifier for an attack made in `self` right after one made in `previous`.
+    /// A fighting-defensively penalty carries over to the next attack even if the stance
+    /// is dropped.
+    pub fn attack_mod_after(self, previous: Stance) -> i32 {
+        match previous {
+            Stance::FightDefensively(_) if previous != self => {
+                self.attack_mod().min(previous.attack_mod())
+            }
+            _ => self.attack_mod(),
+        }
+    }
+}
+
+impl fmt::Display for Stance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stance::Normal => write!(f, "Normal"),
+            Stance::AggressiveAttack => write!(f, "Aggressive attack"),
+            Stance::FightDefensively(level) => write!(
+                f,
+                "Fight defensively ({:+}/{:+})",
+                self.attack_mod(),
+                level.clamp(&1, &4)
+            ),
+        }
+    }
+}
+
+/// A retreat taken in reaction to being attacked in melee.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Retreat {
+    /// Back off at walking pace.
+    GiveGround,
+    /// Back off at jogging pace.
+    ScamperBack,
+}
+
+impl Retreat {
+    pub const ALL: [Retreat; 2] = [Retreat::GiveGround, Retreat::ScamperBack];
+
+    /// Walking and jogging distances from references/movement_actions.md.
+    pub fn feet(self) -> f32 {
+        match self {
+            Retreat::GiveGround => 5.0,
+            Retreat::ScamperBack => 10.0,
+        }
+    }
+
+    /// Bonus against the attack being retreated from.
+    pub fn defense_bonus(self) -> i32 {
+        5
+    }
+
+    /// Penalty carried to the retreating fighter's next attack.
+    pub fn next_attack_mod(self) -> i32 {
+        match self {
+            Retreat::GiveGround => -1,
+            Retreat::ScamperBack => -4,
+        }
+    }
+}
+
+impl fmt::Display for Retreat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Retreat::GiveGround => write!(f, "Give ground"),
+            Retreat::ScamperBack => write!(f, "Scamper back"),
+        }
+    }
+}
+
+/// What a fighter does when their attack comes due.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnAction {
+    #[default]
+    Attack,
+    /// Skip the attack for +5 defense until the next one would have come due.
+    FullParry,
+    /// Jab instead of swinging; needs a weapon that can jab.
+    Jab,
+    /// Draw the reserve weapon in place of the one in hand.
+    SwapWeapon,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fighting_defensively_penalty_outlasts_the_stance() {
+        let defensive = Stance::FightDefensively(3);
+        assert_eq!(defensive.attack_mod(), -6);
+        assert_eq!(defensive.defense_mod(), 3);
+        assert_eq!(Stance::AggressiveAttack.attack_mod_after(defensive), -6);
+        assert_eq!(Stance::Normal.attack_mod_after(defensive), -6);
+        assert_eq!(Stance::FightDefensively(1).attack_mod_after(defensive), -6);
+        assert_eq!(defensive.attack_mod_after(defensive), -6);
+        assert_eq!(Stance::AggressiveAttack.attack_mod_after(Stance::Normal), 5);
+    }
+
+    #[test]
+    fn stances_round_trip_as_json() {
+        for stance in Stance::ALL {
+            let json = serde_json::to_string(&stance).unwrap();
+            assert_eq!(serde_json::from_str::<Stance>(&json).unwrap(), stance);
+        }
+        assert_eq!(
+            serde_json::from_str::<Stance>(r#"{ "fight_defensively": 2 }"#).unwrap(),
+            Stance::FightDefensively(2)
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::arena::Arena;
+use crate::critical::{self, CRITICAL_EFFECTS_PATH, CriticalTable};
+use crate::game_logic::{
+    self, ArmorEntry, NpcPreset, PlayerBuild, PlayerConfig, ShieldEntry, WeaponPreset,
+};
+use crate::sim::{Combatant, RuleToggles, SimConfig};
+
+pub const EXAMPLE_SCENARIO_JSON: &str = include_str!("../data/scenarios/example_duel.json");
+
+/// A fight described on disk: the fighter builds on each side plus the sim settings to run
+/// them under. A duel is one fighter a side.
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    pub sides: [Vec<PlayerBuild>; 2],
+    pub start_distance: f32,
+    /// Engagement distance; derived from the fighters' weapons when omitted, as in the GUI.
+    pub stop_distance: Option<f32>,
+    pub surprise_seconds: [f32; 2],
+    pub hesitation_seconds: [f32; 2],
+    pub seed: Option<u64>,
+    pub rules: RuleToggles,
+    /// The map, in feet: side 0 lines up at `x = 0` and side 1 at `x = start_distance`,
+    /// each rank spread along y around 0.
+    pub arena: Arena,
+}
+
+/// A fighter is either written out inline or loaded from a saved build file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum FighterSource {
+    /// Path to a build file, relative to the scenario file.
+    File { build: PathBuf },
+    /// Parsed as a `PlayerBuild` only after matching, so a bad field (say, a tactics rule
+    /// that doesn't parse) reports its own error.
+    Inline(serde_json::Value),
+}
+
+#[derive(Deserialize)]
+struct ScenarioFile {
+    /// A duel; shorthand for one fighter on each of `sides`.
+    #[serde(default)]
+    fighters: Option<[FighterSource; 2]>,
+    #[serde(default)]
+    sides: Option<[Vec<FighterSource>; 2]>,
+    #[serde(default = "default_start_distance")]
+    start_distance: f32,
+    #[serde(default)]
+    stop_distance: Option<f32>,
+    #[serde(default)]
+    surprise_seconds: [f32; 2],
+    #[serde(default)]
+    hesitation_seconds: [f32; 2],
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    rules: RuleToggles,
+    #[serde(default)]
+    terrain: Arena,
+}
+
+/// The catalogs a scenario's names are resolved against.
+pub struct Catalogs {
+    pub weapons: Vec<WeaponPreset>,
+    pub armor: Vec<ArmorEntry>,
+    pub shields: Vec<ShieldEntry>,
+    pub npc_presets: Vec<NpcPreset>,
+    pub critical_table: CriticalTable,
+}
+
+impl Catalogs {
+    /// Loads the `data/` catalogs, falling back to the built-in ones like the GUI does.
+    pub fn load() -> Result<Self, String> {
+        let (weapons, armor, shields) = game_logic::load_catalogs()?;
+        let npc_presets = game_logic::load_npc_presets("data/npc_presets.json")?;
+        let critical_table = critical::load_critical_table(CRITICAL_EFFECTS_PATH)?;
+        Ok(Self {
+            weapons,
+            armor,
+            shields,
+            npc_presets,
+            critical_table,
+        })
+    }
+
+    pub fn resolve(&self, build: &PlayerBuild) -> Result<PlayerConfig, String> {
+        build.to_player(&self.weapons, &self.armor, &self.shields, &self.npc_presets)
+    }
+}
+
+fn default_start_distance() -> f32 {
+    20.0
+}
+
+pub fn load_scenario(path: &Path) -> Result<Scenario, String> {
+    let data = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    parse_scenario_in(&data, base_dir).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// Parses a scenario, resolving build file paths against the working directory.
+pub fn parse_scenario(data: &str) -> Result<Scenario, String> {
+    parse_scenario_in(data, Path::new(""))
+}
+
+fn parse_scenario_in(data: &str, base_dir: &Path) -> Result<Scenario, String> {
+    let file: ScenarioFile = serde_json::from_str(data).map_err(|err| err.to_string())?;
+    let [first, second] = match (file.fighters, file.sides) {
+        (Some([first, second]), None) => [vec![first], vec![second]],
+        (None, Some(sides)) => sides,
+        (Some(_), Some(_)) => return Err("give either \"fighters\" or \"sides\", not both".into()),
+        (None, None) => return Err("missing \"fighters\" or \"sides\"".into()),
+    };
+    if first.is_empty() || second.is_empty() {
+        return Err("each side needs at least one fighter".into());
+    }
+    let resolve = |sources: Vec<FighterSource>| {
+        sources
+            .into_iter()
+            .map(|source| match source {
+                FighterSource::File { build } => game_logic::load_build(&base_dir.join(build)),
+                FighterSource::Inline(build) => {
+                    serde_json::from_value::<PlayerBuild>(build).map_err(|err| err.to_string())
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    Ok(Scenario {
+        sides: [resolve(first)?, resolve(second)?],
+        start_distance: file.start_distance,
+        stop_distance: file.stop_distance,
+        surprise_seconds: file.surprise_seconds,
+        hesitation_seconds: file.hesitation_seconds,
+        seed: file.seed,
+        rules: file.rules,
+        arena: file.terrain,
+    })
+}
+
+impl Scenario {
+    /// Whether this is a one-on-one fight.
+    pub fn is_duel(&self) -> bool {
+        self.sides.iter().all(|side| side.len() == 1)
+    }
+
+    pub fn players(&self, catalogs: &Catalogs) -> Result<[Vec<PlayerConfig>; 2], String> {
+        let resolve = |side: &[PlayerBuild]| {
+            side.iter()
+                .map(|build| catalogs.resolve(build))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok([resolve(&self.sides[0])?, resolve(&self.sides[1])?])
+    }
+
+    pub fn combatants(&self, catalogs: &Catalogs) -> Result<[Vec<Combatant>; 2], String> {
+        let build = |player: &PlayerConfig| {
+            game_logic::build_combatant(
+                player,
+                &catalogs.weapons,
+                &catalogs.armor,
+                &catalogs.shields,
+                &catalogs.npc_presets,
+            )
+        };
+        let [first, second] = self.players(catalogs)?;
+        Ok([
+            first.iter().map(build).collect(),
+            second.iter().map(build).collect(),
+        ])
+    }
+
+    pub fn sim_config(&self, catalogs: &Catalogs) -> Result<SimConfig, String> {
+        let stop_distance = match self.stop_distance {
+            Some(distance) => distance,
+            None => {
+                let players = self.players(catalogs)?.concat();
+                game_logic::stop_distance_for_players(&players, &catalogs.weapons)
+            }
+        };
+        let mut config = SimConfig::new(self.start_distance, stop_distance);
+        config.surprise_seconds = self.surprise_seconds;
+        config.hesitation_seconds = self.hesitation_seconds;
+        config.rules = self.rules;
+        if let Some(seed) = self.seed {
+            config.seed = seed;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::TerrainKind;
+
+    fn catalogs() -> Catalogs {
+        Catalogs {
+            weapons: game_logic::default_weapon_catalog(),
+            armor: game_logic::default_armor_catalog(),
+            shields: game_logic::default_shield_catalog(),
+            npc_presets: Vec::new(),
+            critical_table: CriticalTable::default(),
+        }
+    }
+
+    #[test]
+    fn example_scenario_builds_like_the_gui() {
+        let catalogs = Catalogs::load().unwrap();
+        let scenario = parse_scenario(EXAMPLE_SCENARIO_JSON).unwrap();
+        let players = scenario.players(&catalogs).unwrap();
+        let combatants = scenario.combatants(&catalogs).unwrap();
+        let direct = game_logic::build_combatant(
+            &players[0][0],
+            &catalogs.weapons,
+            &catalogs.armor,
+            &catalogs.shields,
+            &catalogs.npc_presets,
+        );
+        assert!(scenario.is_duel());
+        assert_eq!(combatants[0][0].name, direct.name);
+        assert_eq!(combatants[0][0].max_hp, direct.max_hp);
+        assert_eq!(combatants[0][0].attack_bonus, direct.attack_bonus);
+        assert_eq!(combatants[0][0].armor_dr, direct.armor_dr);
+        let config = scenario.sim_config(&catalogs).unwrap();
+        assert_eq!(
+            config.stop_distance,
+            game_logic::stop_distance_for_players(&players.concat(), &catalogs.weapons)
+        );
+    }
+
+    #[test]
+    fn missing_fields_fall_back_to_player_defaults() {
+        let scenario = parse_scenario(
+            r#"{
+                "fighters": [{ "name": "A", "weapon": "Fist" }, { "name": "B" }],
+                "seed": 9,
+                "rules": { "reach_delay": false }
+            }"#,
+        )
+        .unwrap();
+        let config = scenario.sim_config(&catalogs()).unwrap();
+        assert_eq!(config.seed, 9);
+        assert_eq!(config.start_distance, 20.0);
+        assert!(!config.rules.reach_delay);
+        assert!(config.rules.shield_breakage);
+        let player = catalogs().resolve(&scenario.sides[1][0]).unwrap();
+        assert_eq!(player.name, "B");
+        assert_eq!(player.base_hp, 10);
+    }
+
+    #[test]
+    fn scripted_tactics_scenario_loads_its_rules() {
+        let catalogs = Catalogs::load().unwrap();
+        let scenario = load_scenario(Path::new("data/scenarios/scripted_tactics.json")).unwrap();
+        assert_eq!(scenario.sides[0][0].tactics_rules.len(), 3);
+        let combatants = scenario.combatants(&catalogs).unwrap();
+        assert_eq!(combatants[0][0].tactics.name(), "Scripted");
+        assert_eq!(combatants[1][0].tactics.name(), "Scripted");
+
+        let err = parse_scenario(
+            r#"{ "fighters": [{ "tactics_rules": ["if hp < 3 then dance"] }, {}] }"#,
+        )
+        .unwrap_err();
+        assert!(err.contains("unknown action \"dance\""), "{err}");
+    }
+
+    #[test]
+    fn fighters_can_reference_build_files() {
+        let dir = std::env::temp_dir().join(format!("hm_scenario_{}", std::process::id()));
+        let build = PlayerBuild {
+            name: "Saved".to_string(),
+            level: 3,
+            weapon: "Dagger".to_string(),
+            ..Default::default()
+        };
+        game_logic::save_build(&dir.join("builds/saved.json"), &build).unwrap();
+        let scenario_path = dir.join("duel.json");
+        fs::write(
+            &scenario_path,
+            r#"{ "fighters": [{ "build": "builds/saved.json" }, { "name": "Inline" }] }"#,
+        )
+        .unwrap();
+        let scenario = load_scenario(&scenario_path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(scenario.sides[0], vec![build]);
+        assert_eq!(scenario.sides[1][0].name, "Inline");
+    }
+
+    #[test]
+    fn battles_list_each_side() {
+        let catalogs = Catalogs::load().unwrap();
+        let scenario = load_scenario(Path::new("data/scenarios/party_vs_hobgoblins.json")).unwrap();
+        assert!(!scenario.is_duel());
+        let [party, hobgoblins] = scenario.combatants(&catalogs).unwrap();
+        assert_eq!(party.len(), 2);
+        assert_eq!(hobgoblins.len(), 3);
+        assert_eq!(hobgoblins[2].name, "Hobgoblin 3");
+
+        let err = parse_scenario(r#"{ "sides": [[{ "name": "Alone" }], []] }"#).unwrap_err();
+        assert!(err.contains("at least one fighter"), "{err}");
+    }
+
+    #[test]
+    fn scenarios_lay_terrain_over_the_field() {
+        let scenario = load_scenario(Path::new("data/scenarios/archer_behind_wall.json")).unwrap();
+        assert_eq!(scenario.arena.terrain.len(), 3);
+        assert!(
+            scenario
+                .arena
+                .terrain
+                .iter()
+                .any(|feature| feature.kind == TerrainKind::Wall)
+        );
+        assert!(
+            parse_scenario(EXAMPLE_SCENARIO_JSON)
+                .unwrap()
+                .arena
+                .terrain
+                .is_empty()
+        );
+    }
+}
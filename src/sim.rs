@@ -1,9 +1,67 @@
+use std::sync::Arc;
+
 use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug)]
+use crate::arena::{Arena, Point};
+use crate::critical::{CriticalHit, CriticalTable, DefenseResult, Fumble};
+use crate::fighting_style::{FightingStyle, OFF_HAND_DAMAGE_PENALTY, OFF_HAND_DELAY_SECONDS};
+use crate::maneuver::{Retreat, Stance, TurnAction};
+use crate::status::{StatusKind, Statuses};
+use crate::tactics::{Intent, Movement, Situation, StandardTactics, Tactics, TargetPriority};
+
+/// Optional rules that can be switched off to compare against a plainer fight.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleToggles {
+    /// The shorter weapon loses a second closing past the longer one's reach.
+    pub reach_delay: bool,
+    /// Blocked blows can break the shield per its breakage thresholds.
+    pub shield_breakage: bool,
+    /// A blow past the defender's threshold of pain forces a trauma save.
+    pub threshold_of_pain: bool,
+    /// Heavy blows push the defender back and can knock them prone.
+    pub knockback: bool,
+    /// A natural 20 that hits rolls on the critical effects table.
+    pub criticals: bool,
+    /// A natural 1 on the attack die rolls on the fumble table.
+    pub fumbles: bool,
+    /// A natural 20 on a d20 defense turns any attack; a natural 19 halves its damage.
+    pub perfect_defense: bool,
+    /// A longer weapon holds an entering opponent at bay until they knock it aside.
+    pub hold_at_bay: bool,
+    /// Several attackers or one from behind worsen the defense die, and a shield only
+    /// guards the front.
+    pub flanking: bool,
+}
+
+impl Default for RuleToggles {
+    fn default() -> Self {
+        Self {
+            reach_delay: true,
+            shield_breakage: true,
+            threshold_of_pain: true,
+            knockback: true,
+            criticals: true,
+            fumbles: true,
+            perfect_defense: true,
+            hold_at_bay: true,
+            flanking: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
 pub struct SimConfig {
     pub start_distance: f32,
     pub stop_distance: f32,
+    /// Seconds added to each side's initiative count for being caught unaware.
+    pub surprise_seconds: [f32; 2],
+    /// Seconds each side waits before committing to the fight, on top of initiative.
+    pub hesitation_seconds: [f32; 2],
+    /// RNG seed applied on every reset; the same seed replays the same fight.
+    pub seed: u64,
+    pub rules: RuleToggles,
 }
 
 impl SimConfig {
@@ -11,13 +69,32 @@ impl SimConfig {
         Self {
             start_distance,
             stop_distance,
+            surprise_seconds: [0.0; 2],
+            hesitation_seconds: [0.0; 2],
+            seed: 1,
+            rules: RuleToggles::default(),
         }
     }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn initiative_offset(&self, side: usize) -> f32 {
+        self.surprise_seconds[side].max(0.0) + self.hesitation_seconds[side].max(0.0)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct SimActor {
-    pub position: f32,
+    pub position: Point,
+    /// Unit vector the fighter faces.
+    pub facing: Point,
+    /// 0 or 1; each side starts in a rank at its own end of the field.
+    pub side: usize,
+    /// The enemy this fighter is fighting, chosen anew when they fall.
+    pub target: Option<usize>,
 }
 
 #[derive(Clone, Debug)]
@@ -42,6 +119,9 @@ pub struct Combatant {
     pub weapon_defense_always: bool,
     pub max_hp: i32,
     pub hp: i32,
+    pub initiative_die_sides: i32,
+    pub initiative_mod: i32,
+    pub initiative_count: Option<f32>,
     pub next_attack_time: Option<f32>,
     pub defense_plus_four_ready: bool,
     pub moved_last_tick: bool,
@@ -51,8 +131,121 @@ pub struct Combatant {
     pub shield_cover_value: Option<i32>,
     pub shield_intact: bool,
     pub shield_breakage: Option<[ShieldBreakageStep; 4]>,
+    /// HP damage a single blow must exceed to force a trauma save; `None` never checks.
+    pub threshold_of_pain: Option<i32>,
+    /// Trauma saves succeed on a d20 at or under this (the fighter's Con).
+    pub trauma_save: i32,
+    pub statuses: Statuses,
+    /// The fighter holding this one at bay, while `StatusKind::HeldAtBay` lasts.
+    pub held_by: Option<usize>,
+    pub stance: Stance,
+    /// The stance declared with the last attack; its defense modifier holds until the next.
+    pub active_stance: Stance,
+    pub turn_action: TurnAction,
+    /// Taken whenever attacked in melee, if the rules allow it.
+    pub retreat: Option<Retreat>,
+    /// Carried to the next attack, e.g. from giving ground.
+    pub pending_attack_mod: i32,
+    /// Charges into contact when the approach gives enough of a run-up.
+    pub charge: bool,
+    /// Holds ground and braces the weapon against a charging opponent.
+    pub set_for_charge: bool,
+    /// The Dex part of `defense_mod`, lost for a few seconds after charging.
+    pub dex_defense: i32,
+    /// Feet run straight at the opponent without stopping.
+    pub run_feet: f32,
+    /// Seconds spent standing still.
+    pub braced_seconds: f32,
+    /// The next attack is a charge.
+    pub charging: bool,
+    /// The next attack is a set-for-charge strike, rolling the weapon dice twice.
+    pub set_strike: bool,
+    /// Where the attack now being resolved against this fighter comes from.
+    pub attacked_from: AttackPosition,
+    /// Cover value of the terrain between this fighter and the shot now being resolved.
+    pub behind_cover: Option<i32>,
+    /// Jab speed of the weapon in hand; `None` if it cannot jab. A weapon that can jab
+    /// may only jab an opponent it holds at bay: half damage, or the jab special, without
+    /// penetration and at this speed.
+    pub jab_speed: Option<f32>,
+    /// Seconds to draw the weapon in hand during melee.
+    pub draw_seconds: f32,
+    /// Carried besides the weapon in hand, ready for a weapon swap.
+    pub reserve_weapon: Option<WeaponLoadout>,
+    /// The reserve weapon is in hand; swapped back on reset.
+    pub weapon_swapped: bool,
+    /// The weapon in hand is one-handed but big enough to grip with both hands.
+    pub can_two_hand: bool,
+    /// Switched to a two-handed grip mid-fight; undone on reset.
+    pub regripped: bool,
+    /// The style chosen for the fight; `current_style` is the one the gear still allows.
+    pub fighting_style: FightingStyle,
+    /// The second weapon of a two-weapon style.
+    pub off_hand: Option<WeaponLoadout>,
+    /// The off-hand weapon is smaller than its wielder; a near-perfect defense with it
+    /// still lets the whole blow through.
+    pub off_hand_small: bool,
+    pub next_off_hand_time: Option<f32>,
+    /// Defense bonus of the second shield in the two-shield style.
+    pub second_shield_defense_bonus: i32,
+    /// How a new target is picked in a battle.
+    pub target_priority: TargetPriority,
+    pub tactics: Arc<dyn Tactics>,
+    /// This second's decision from `tactics`; `None` until the first is made.
+    pub intent: Option<Intent>,
+}
+
+/// The parts of a combatant that come with the weapon in hand, so a reserve weapon can
+/// be swapped in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeaponLoadout {
+    pub weapon_name: String,
+    pub damage_expr: String,
+    pub shield_damage_expr: Option<String>,
+    pub strength_damage: i32,
+    pub armor_penetration: i32,
+    pub weapon_speed: f32,
+    pub reach_ft: f32,
+    pub two_hand_grip: bool,
+    pub use_jab: bool,
+    pub jab_speed: Option<f32>,
+    pub jab_special_expr: Option<String>,
+    pub has_weapon: bool,
+    pub weapon_defense_always: bool,
+    pub set_for_charge: bool,
+    pub draw_seconds: f32,
 }
 
+/// Seconds a failed trauma save leaves the fighter writhing on the ground.
+const PAIN_INCAPACITATION_EXPR: &str = "5d4p";
+/// Damage from a single blow needed to knock a man-sized fighter back.
+const KNOCKBACK_THRESHOLD: i32 = 10;
+/// Feet pushed back per full knockback threshold of damage.
+const KNOCKBACK_FEET: f32 = 5.0;
+/// Standing up takes a second (references/movement_actions.md).
+const STAND_UP_SECONDS: f32 = 1.0;
+/// Straight-line run a charge needs before contact.
+const CHARGE_RUN_FEET: f32 = 20.0;
+const CHARGE_ATTACK_BONUS: i32 = 4;
+/// Seconds after contact that a charger fights without their Dex bonus to defense.
+const CHARGE_EXPOSED_SECONDS: f32 = 5.0;
+/// Seconds a set weapon must be braced before the charger makes contact.
+const SET_FOR_CHARGE_BRACE_SECONDS: f32 = 3.0;
+/// A retreating fighter backs off at walking pace.
+const RETREAT_STEP_FEET: f32 = 5.0;
+/// Cosine of the half-angle of the front arc (60 degrees either side): a shield covers it,
+/// and a target inside it is tracked without a turn.
+const FRONT_ARC_COS: f32 = 0.5;
+/// Attacks from within 45 degrees of straight behind come from the rear.
+const REAR_ARC_COS: f32 = -std::f32::consts::FRAC_1_SQRT_2;
+/// Changing facing takes a second (references/movement_actions.md).
+const TURN_SECONDS: f32 = 1.0;
+/// Feet between fighters standing side by side at the start.
+const RANK_SPACING_FEET: f32 = 5.0;
+/// Damage and speed added by gripping a one-handed weapon with both hands.
+pub const TWO_HAND_GRIP_DAMAGE: i32 = 3;
+pub const TWO_HAND_GRIP_SPEED: f32 = 2.0;
+
 #[derive(Clone, Copy, Debug)]
 pub struct ShieldBreakageStep {
     pub threshold: i32,
@@ -108,6 +301,9 @@ impl Combatant {
             weapon_defense_always,
             max_hp,
             hp: max_hp,
+            initiative_die_sides: 12,
+            initiative_mod: 0,
+            initiative_count: None,
             next_attack_time: None,
             defense_plus_four_ready: false,
             moved_last_tick: false,
@@ -117,15 +313,243 @@ impl Combatant {
             shield_cover_value,
             shield_intact,
             shield_breakage,
+            threshold_of_pain: None,
+            trauma_save: 10,
+            statuses: Statuses::default(),
+            held_by: None,
+            stance: Stance::Normal,
+            active_stance: Stance::Normal,
+            turn_action: TurnAction::Attack,
+            retreat: None,
+            pending_attack_mod: 0,
+            attacked_from: AttackPosition::default(),
+            behind_cover: None,
+            charge: false,
+            set_for_charge: false,
+            dex_defense: 0,
+            run_feet: 0.0,
+            braced_seconds: 0.0,
+            charging: false,
+            set_strike: false,
+            jab_speed: None,
+            draw_seconds: 1.0,
+            reserve_weapon: None,
+            weapon_swapped: false,
+            can_two_hand: false,
+            regripped: false,
+            fighting_style: FightingStyle::OneHanded,
+            off_hand: None,
+            off_hand_small: false,
+            next_off_hand_time: None,
+            second_shield_defense_bonus: 0,
+            target_priority: TargetPriority::Nearest,
+            tactics: Arc::new(StandardTactics),
+            intent: None,
+        }
+    }
+
+    pub fn with_initiative(mut self, die_sides: i32, modifier: i32) -> Self {
+        self.initiative_die_sides = die_sides;
+        self.initiative_mod = modifier;
+        self
+    }
+
+    pub fn with_threshold_of_pain(mut self, threshold: i32, trauma_save: i32) -> Self {
+        self.threshold_of_pain = Some(threshold);
+        self.trauma_save = trauma_save;
+        self
+    }
+
+    pub fn with_stance(mut self, stance: Stance) -> Self {
+        self.stance = stance;
+        self
+    }
+
+    pub fn with_retreat(mut self, retreat: Option<Retreat>) -> Self {
+        self.retreat = retreat;
+        self
+    }
+
+    pub fn with_charge(mut self, charge: bool, set_for_charge: bool, dex_defense: i32) -> Self {
+        self.charge = charge;
+        self.set_for_charge = set_for_charge;
+        self.dex_defense = dex_defense;
+        self
+    }
+
+    pub fn with_jab_speed(mut self, jab_speed: Option<f32>) -> Self {
+        self.jab_speed = jab_speed;
+        self
+    }
+
+    pub fn with_draw_seconds(mut self, draw_seconds: f32) -> Self {
+        self.draw_seconds = draw_seconds;
+        self
+    }
+
+    pub fn with_reserve_weapon(mut self, reserve_weapon: Option<WeaponLoadout>) -> Self {
+        self.reserve_weapon = reserve_weapon;
+        self
+    }
+
+    pub fn with_can_two_hand(mut self, can_two_hand: bool) -> Self {
+        self.can_two_hand = can_two_hand;
+        self
+    }
+
+    pub fn with_fighting_style(mut self, fighting_style: FightingStyle) -> Self {
+        self.fighting_style = fighting_style;
+        self
+    }
+
+    pub fn with_off_hand(mut self, off_hand: Option<WeaponLoadout>, small: bool) -> Self {
+        self.off_hand = off_hand;
+        self.off_hand_small = small;
+        self
+    }
+
+    pub fn with_second_shield(mut self, defense_bonus: i32) -> Self {
+        self.second_shield_defense_bonus = defense_bonus;
+        self
+    }
+
+    pub fn with_target_priority(mut self, target_priority: TargetPriority) -> Self {
+        self.target_priority = target_priority;
+        self
+    }
+
+    pub fn with_tactics(mut self, tactics: Arc<dyn Tactics>) -> Self {
+        self.tactics = tactics;
+        self
+    }
+
+    /// The stance for the next attack: the tactics' choice, or the configured stance.
+    pub fn intended_stance(&self) -> Stance {
+        self.intent.map_or(self.stance, |intent| intent.stance)
+    }
+
+    pub fn intended_action(&self) -> TurnAction {
+        self.intent.map_or(self.turn_action, |intent| intent.action)
+    }
+
+    pub fn intends_charge(&self) -> bool {
+        self.intent.map_or(self.charge, |intent| intent.charge)
+    }
+
+    /// Whether the next attack is a jab, either by habit or by choice this second.
+    fn jabbing(&self) -> bool {
+        self.use_jab || (self.intended_action() == TurnAction::Jab && self.jab_speed.is_some())
+    }
+
+    /// Weapon speed for the next attack; a chosen jab, or the only jab allowed against an
+    /// opponent held at bay, uses the jab speed.
+    fn attack_speed(&self, target_held: bool) -> f32 {
+        match self.jab_speed {
+            Some(jab_speed) if target_held || (!self.use_jab && self.jabbing()) => jab_speed,
+            _ => self.weapon_speed,
+        }
+    }
+
+    /// The style in use: the chosen one while the gear for it is in hand, otherwise
+    /// whatever the gear left implies, e.g. once the shield breaks or the weapons change.
+    pub fn current_style(&self) -> FightingStyle {
+        let style = self.fighting_style;
+        let holds = if style.uses_two_weapons() {
+            self.off_hand.is_some() && !self.weapon_swapped && !self.two_hand_grip
+        } else {
+            matches!(style, FightingStyle::ShieldOnly | FightingStyle::TwoShields)
+                && self.shield_intact
+        };
+        if holds {
+            style
+        } else {
+            FightingStyle::for_hands(self.shield_intact, self.two_hand_grip, self.can_two_hand)
+        }
+    }
+
+    /// Trades the weapon in hand for the reserve weapon. Returns false without one.
+    pub fn swap_weapon(&mut self) -> bool {
+        let Some(mut loadout) = self.reserve_weapon.take() else {
+            return false;
+        };
+        self.exchange_loadout(&mut loadout);
+        self.reserve_weapon = Some(loadout);
+        self.weapon_swapped = !self.weapon_swapped;
+        self.defense_plus_four_ready = false;
+        true
+    }
+
+    /// Puts `loadout` in hand, leaving the weapon that was there in `loadout`.
+    fn exchange_loadout(&mut self, loadout: &mut WeaponLoadout) {
+        std::mem::swap(&mut self.weapon_name, &mut loadout.weapon_name);
+        std::mem::swap(&mut self.damage_expr, &mut loadout.damage_expr);
+        std::mem::swap(
+            &mut self.shield_damage_expr,
+            &mut loadout.shield_damage_expr,
+        );
+        std::mem::swap(&mut self.strength_damage, &mut loadout.strength_damage);
+        std::mem::swap(&mut self.armor_penetration, &mut loadout.armor_penetration);
+        std::mem::swap(&mut self.weapon_speed, &mut loadout.weapon_speed);
+        std::mem::swap(&mut self.reach_ft, &mut loadout.reach_ft);
+        std::mem::swap(&mut self.two_hand_grip, &mut loadout.two_hand_grip);
+        std::mem::swap(&mut self.use_jab, &mut loadout.use_jab);
+        std::mem::swap(&mut self.jab_speed, &mut loadout.jab_speed);
+        std::mem::swap(&mut self.jab_special_expr, &mut loadout.jab_special_expr);
+        std::mem::swap(&mut self.has_weapon, &mut loadout.has_weapon);
+        std::mem::swap(
+            &mut self.weapon_defense_always,
+            &mut loadout.weapon_defense_always,
+        );
+        std::mem::swap(&mut self.set_for_charge, &mut loadout.set_for_charge);
+        std::mem::swap(&mut self.draw_seconds, &mut loadout.draw_seconds);
+    }
+
+    /// Takes the weapon in hand in both hands for the two-hand grip bonuses. Returns false
+    /// if it already is, cannot be, or an intact shield is in the way.
+    pub fn take_two_hand_grip(&mut self) -> bool {
+        if !self.can_two_hand
+            || self.two_hand_grip
+            || self.weapon_swapped
+            || !self.has_weapon
+            || self.shield_intact
+        {
+            return false;
         }
+        self.two_hand_grip = true;
+        self.strength_damage += TWO_HAND_GRIP_DAMAGE;
+        self.weapon_speed += TWO_HAND_GRIP_SPEED;
+        self.regripped = true;
+        true
     }
 
     fn reset_hp(&mut self) {
         self.hp = self.max_hp;
+        self.initiative_count = None;
         self.next_attack_time = None;
+        self.next_off_hand_time = None;
         self.defense_plus_four_ready = false;
         self.moved_last_tick = false;
         self.shield_intact = self.shield_name.is_some();
+        self.statuses.clear();
+        self.held_by = None;
+        self.active_stance = Stance::Normal;
+        self.pending_attack_mod = 0;
+        self.run_feet = 0.0;
+        self.braced_seconds = 0.0;
+        self.charging = false;
+        self.set_strike = false;
+        self.attacked_from = AttackPosition::default();
+        self.behind_cover = None;
+        if self.weapon_swapped {
+            self.swap_weapon();
+        }
+        if self.regripped {
+            self.two_hand_grip = false;
+            self.strength_damage -= TWO_HAND_GRIP_DAMAGE;
+            self.weapon_speed -= TWO_HAND_GRIP_SPEED;
+            self.regripped = false;
+        }
+        self.intent = None;
     }
 }
 
@@ -152,6 +576,9 @@ impl Default for Combatant {
             weapon_defense_always: false,
             max_hp: 10,
             hp: 10,
+            initiative_die_sides: 12,
+            initiative_mod: 0,
+            initiative_count: None,
             next_attack_time: None,
             defense_plus_four_ready: false,
             moved_last_tick: false,
@@ -161,6 +588,38 @@ impl Default for Combatant {
             shield_cover_value: None,
             shield_intact: false,
             shield_breakage: None,
+            threshold_of_pain: None,
+            trauma_save: 10,
+            statuses: Statuses::default(),
+            held_by: None,
+            stance: Stance::Normal,
+            active_stance: Stance::Normal,
+            turn_action: TurnAction::Attack,
+            retreat: None,
+            pending_attack_mod: 0,
+            attacked_from: AttackPosition::default(),
+            behind_cover: None,
+            charge: false,
+            set_for_charge: false,
+            dex_defense: 0,
+            run_feet: 0.0,
+            braced_seconds: 0.0,
+            charging: false,
+            set_strike: false,
+            jab_speed: None,
+            draw_seconds: 1.0,
+            reserve_weapon: None,
+            weapon_swapped: false,
+            can_two_hand: false,
+            regripped: false,
+            fighting_style: FightingStyle::OneHanded,
+            off_hand: None,
+            off_hand_small: false,
+            next_off_hand_time: None,
+            second_shield_defense_bonus: 0,
+            target_priority: TargetPriority::Nearest,
+            tactics: Arc::new(StandardTactics),
+            intent: None,
         }
     }
 }
@@ -294,6 +753,11 @@ pub fn max_range_for_weapon(name: &str) -> Option<f32> {
     })
 }
 
+/// Draws a fresh seed for exploring variance; log it to replay the fight later.
+pub fn random_seed() -> u64 {
+    rand::random()
+}
+
 fn defense_die_sides(is_ranged: bool, defender_moved_last_tick: bool, has_shield: bool) -> i32 {
     if is_ranged {
         if has_shield {
@@ -308,15 +772,534 @@ fn defense_die_sides(is_ranged: bool, defender_moved_last_tick: bool, has_shield
     }
 }
 
+/// Which way an attack comes at the defender, relative to the way they face.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttackAngle {
+    #[default]
+    Front,
+    Flank,
+    Rear,
+}
+
+/// An attack's place among everyone attacking the defender at once, which picks the
+/// defense die per the facing and flanks table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct AttackPosition {
+    pub angle: AttackAngle,
+    /// Order among the attackers to the defender's front and flanks, from 1.
+    pub order: usize,
+    /// Melee attackers on the defender from any direction.
+    pub attackers: usize,
+}
+
+impl Default for AttackPosition {
+    fn default() -> Self {
+        Self {
+            angle: AttackAngle::Front,
+            order: 1,
+            attackers: 1,
+        }
+    }
+}
+
+impl AttackPosition {
+    /// Melee defense die and its modifier: d8p from the rear, d12p for a fourth or later
+    /// attacker to the front and flanks, and the usual d20p against the first three. The
+    /// table's d20p-4 is the weapon defense without its +4, applied in `resolve_attack`.
+    pub fn defense_die(self) -> (i32, i32) {
+        match self.angle {
+            AttackAngle::Rear => (8, 0),
+            _ if self.order >= 4 => (12, 0),
+            _ => (20, 0),
+        }
+    }
+
+    /// A shield is only held between the defender and attacks from the front; a pair of
+    /// shields guards the flanks as well.
+    pub fn shield_covers(self, style: FightingStyle) -> bool {
+        match self.angle {
+            AttackAngle::Front => true,
+            AttackAngle::Flank => style == FightingStyle::TwoShields,
+            AttackAngle::Rear => false,
+        }
+    }
+}
+
+/// Everything rolled for one attack, shared by the hit, miss and block events.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AttackRoll {
+    pub attacker: usize,
+    pub defender: usize,
+    pub attacker_name: String,
+    pub defender_name: String,
+    pub weapon_name: String,
+    pub is_ranged: bool,
+    pub stance: Stance,
+    /// A charge attack; its bonus is included in `attack_bonus`.
+    pub charge: bool,
+    /// A set-for-charge strike with doubled weapon dice.
+    pub set_for_charge: bool,
+    /// A weapon that cannot jab, keeping a held opponent at bay; a hit does no damage.
+    pub keeping_at_bay: bool,
+    pub attack_die: i32,
+    pub attack_bonus: i32,
+    pub range_mod: i32,
+    pub cover_cap: Option<i32>,
+    pub attack_total: i32,
+    pub position: AttackPosition,
+    pub defense_die_sides: i32,
+    pub defense_die: i32,
+    /// From being outnumbered; included in `defense_total` but not `defense_mod`.
+    pub defense_die_mod: i32,
+    pub defense_mod: i32,
+    pub weapon_defense_bonus: i32,
+    pub shield_defense_bonus: i32,
+    pub defense_total: i32,
+    pub defense_result: Option<DefenseResult>,
+    /// Included in `defense_mod`.
+    pub retreat: Option<Retreat>,
+}
+
+impl std::fmt::Display for AttackRoll {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "atk {} [d20p={}] vs def {} [d{}p{}={}]",
+            self.attack_total,
+            self.attack_die,
+            self.defense_total,
+            self.defense_die_sides,
+            match self.defense_die_mod {
+                0 => String::new(),
+                modifier => format!("{modifier:+}"),
+            },
+            self.defense_die
+        )?;
+        match self.position.angle {
+            AttackAngle::Front => {}
+            AttackAngle::Flank => write!(f, " from the flank")?,
+            AttackAngle::Rear => write!(f, " from the rear")?,
+        }
+        if let Some(cap) = self.cover_cap {
+            write!(f, " behind cover (max {cap})")?;
+        }
+        if self.stance != Stance::Normal {
+            write!(f, " {}", self.stance.to_string().to_lowercase())?;
+        }
+        if self.charge {
+            write!(f, " charge")?;
+        }
+        if self.set_for_charge {
+            write!(f, " set vs charge")?;
+        }
+        if self.keeping_at_bay {
+            write!(f, " keeping at bay")?;
+        }
+        if let Some(retreat) = self.retreat {
+            write!(f, " {}", retreat.to_string().to_lowercase())?;
+        }
+        match self.defense_result {
+            Some(result) => write!(f, " {}", result.label()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DamageRoll {
+    /// Dice breakdown, e.g. `[2d8=3+8]`.
+    pub detail: String,
+    pub rolled: i32,
+    pub modifier: i32,
+    /// Rolled plus modifier, floored at zero, before any DR.
+    pub raw: i32,
+    /// DR actually subtracted (armor after penetration, plus shield DR on a block).
+    pub dr_applied: i32,
+    /// Hit points lost by the defender.
+    pub damage: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CombatEvent {
+    Seed {
+        seed: u64,
+    },
+    Initiative {
+        combatant: usize,
+        name: String,
+        die_sides: i32,
+        die: i32,
+        modifier: i32,
+        count: f32,
+    },
+    Move {
+        combatant: usize,
+        name: String,
+        from: Point,
+        to: Point,
+    },
+    /// Turned to face a target outside the front arc, taking the second.
+    Turn {
+        combatant: usize,
+        name: String,
+    },
+    Hit {
+        roll: AttackRoll,
+        /// Includes any critical damage.
+        damage: DamageRoll,
+        hp_after: i32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        critical: Option<CriticalHit>,
+    },
+    Miss {
+        roll: AttackRoll,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fumble: Option<Fumble>,
+    },
+    ShieldBlock {
+        roll: AttackRoll,
+        shield_name: String,
+        shield_damage: DamageRoll,
+        shield_broken: bool,
+        hp_after: i32,
+    },
+    ShieldBroken {
+        combatant: usize,
+        name: String,
+        shield_name: String,
+    },
+    ThresholdOfPain {
+        combatant: usize,
+        name: String,
+        damage: i32,
+        threshold: i32,
+        save_roll: i32,
+        save_target: i32,
+        /// `None` when the save held.
+        incapacitated_seconds: Option<i32>,
+    },
+    StatusEnded {
+        combatant: usize,
+        name: String,
+        status: StatusKind,
+    },
+    FullParry {
+        combatant: usize,
+        name: String,
+    },
+    Charge {
+        combatant: usize,
+        name: String,
+        /// Straight-line run before contact.
+        feet: f32,
+    },
+    /// A set weapon hit the charger, who loses the charge and is held at bay.
+    ChargeBroken {
+        combatant: usize,
+        name: String,
+    },
+    HeldAtBay {
+        combatant: usize,
+        name: String,
+        holder: String,
+    },
+    WeaponSwap {
+        combatant: usize,
+        name: String,
+        weapon_name: String,
+        /// When the newly drawn weapon can first attack.
+        ready_at: f32,
+    },
+    TwoHandGrip {
+        combatant: usize,
+        name: String,
+        weapon_name: String,
+    },
+    /// A held fighter's d20p attack against the holder's shieldless defense.
+    KnockAside {
+        combatant: usize,
+        name: String,
+        holder: String,
+        attack_die: i32,
+        attack_total: i32,
+        defense_die: i32,
+        defense_total: i32,
+        success: bool,
+    },
+    Knockback {
+        combatant: usize,
+        name: String,
+        /// Damage counted for knockback; Charge damage counts double.
+        damage: i32,
+        feet: f32,
+        /// d20p the defender rolls against the damage to keep their feet.
+        balance_roll: i32,
+        knocked_down: bool,
+    },
+    Defeat {
+        combatant: usize,
+        name: String,
+    },
+}
+
+impl CombatEvent {
+    pub fn attack_roll(&self) -> Option<&AttackRoll> {
+        match self {
+            CombatEvent::Hit { roll, .. }
+            | CombatEvent::Miss { roll, .. }
+            | CombatEvent::ShieldBlock { roll, .. } => Some(roll),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for CombatEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CombatEvent::Seed { seed } => write!(f, "Seed: {seed}"),
+            CombatEvent::Initiative {
+                name,
+                die_sides,
+                die,
+                count,
+                ..
+            } => write!(f, "{name} initiative {count} [d{die_sides}p={die}]"),
+            CombatEvent::Move { name, from, to, .. } => {
+                write!(f, "{name} moves {:.1} ft", from.distance(*to))
+            }
+            CombatEvent::Turn { name, .. } => write!(f, "{name} turns to face their target"),
+            CombatEvent::Hit {
+                roll,
+                damage,
+                hp_after,
+                critical,
+            } => {
+                write!(
+                    f,
+                    "{} hits {} with {} ({}) for {} dmg {}",
+                    roll.attacker_name,
+                    roll.defender_name,
+                    roll.weapon_name,
+                    roll,
+                    damage.damage,
+                    damage.detail,
+                )?;
+                if let Some(critical) = critical {
+                    write!(
+                        f,
+                        " CRITICAL {} (severity {}, +{} dmg {})",
+                        critical.name, critical.severity, critical.extra_damage, critical.detail
+                    )?;
+                }
+                write!(f, " (hp {})", hp_after.max(&0))
+            }
+            CombatEvent::Miss { roll, fumble } => {
+                write!(
+                    f,
+                    "{} misses {} with {} ({})",
+                    roll.attacker_name, roll.defender_name, roll.weapon_name, roll
+                )?;
+                match fumble {
+                    Some(fumble) => write!(f, " FUMBLE {} [d20={}]", fumble.name, fumble.roll),
+                    None => Ok(()),
+                }
+            }
+            CombatEvent::ShieldBlock {
+                roll,
+                shield_name,
+                shield_damage,
+                shield_broken,
+                hp_after,
+            } => write!(
+                f,
+                "{} blocks {} with {} ({}); shield dmg {} {} ({}), hp {}",
+                roll.defender_name,
+                roll.attacker_name,
+                shield_name,
+                roll,
+                shield_damage.raw,
+                shield_damage.detail,
+                if *shield_broken {
+                    "shield broken"
+                } else {
+                    "shield intact"
+                },
+                hp_after.max(&0)
+            ),
+            CombatEvent::ShieldBroken {
+                name, shield_name, ..
+            } => write!(f, "{name}'s {shield_name} is destroyed"),
+            CombatEvent::ThresholdOfPain {
+                name,
+                damage,
+                threshold,
+                save_roll,
+                save_target,
+                incapacitated_seconds,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{name} takes {damage} past ToP {threshold}, trauma save d20={save_roll} vs {save_target}"
+                )?;
+                match incapacitated_seconds {
+                    Some(seconds) => write!(f, " fails: incapacitated {seconds}s"),
+                    None => write!(f, " holds"),
+                }
+            }
+            CombatEvent::StatusEnded { name, status, .. } => match status {
+                StatusKind::Prone => write!(f, "{name} is back on their feet"),
+                StatusKind::FullParry => write!(f, "{name} drops their parry"),
+                StatusKind::Charging => write!(f, "{name} recovers from the charge"),
+                _ => write!(f, "{name} is no longer {}", status.label().to_lowercase()),
+            },
+            CombatEvent::FullParry { name, .. } => {
+                write!(f, "{name} skips the attack for a full parry")
+            }
+            CombatEvent::Charge { name, feet, .. } => {
+                write!(f, "{name} charges after a {feet:.0} ft run")
+            }
+            CombatEvent::ChargeBroken { name, .. } => {
+                write!(f, "{name}'s charge is broken and they are held at bay")
+            }
+            CombatEvent::HeldAtBay { name, holder, .. } => {
+                write!(f, "{name} is held at bay by {holder}")
+            }
+            CombatEvent::WeaponSwap {
+                name,
+                weapon_name,
+                ready_at,
+                ..
+            } => write!(f, "{name} draws {weapon_name}, ready at t={ready_at:.0}s"),
+            CombatEvent::TwoHandGrip {
+                name, weapon_name, ..
+            } => write!(f, "{name} takes {weapon_name} in both hands"),
+            CombatEvent::KnockAside {
+                name,
+                holder,
+                attack_die,
+                attack_total,
+                defense_die,
+                defense_total,
+                success,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{name} tries to knock aside {holder}'s weapon: atk {attack_total} [d20p={attack_die}] vs def {defense_total} [d20p={defense_die}]"
+                )?;
+                if *success {
+                    write!(f, ", and slips inside")
+                } else {
+                    write!(f, ", and is still held")
+                }
+            }
+            CombatEvent::Knockback {
+                name,
+                damage,
+                feet,
+                balance_roll,
+                knocked_down,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{name} is knocked back {feet:.0} ft by {damage} dmg, balance d20p={balance_roll}"
+                )?;
+                if *knocked_down {
+                    write!(f, " and falls prone")
+                } else {
+                    write!(f, " and keeps their feet")
+                }
+            }
+            CombatEvent::Defeat { name, .. } => write!(f, "{name} is down"),
+        }
+    }
+}
+
+/// The events resolved during one second of the count.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LogEntry {
+    pub time: u32,
+    pub events: Vec<CombatEvent>,
+}
+
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "t={}s", self.time)?;
+        for event in &self.events {
+            write!(f, " | {event}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Per-side tallies for a single fight.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct FightStats {
+    pub attacks: u32,
+    pub hits: u32,
+    pub shield_blocks: u32,
+    pub damage_dealt: i32,
+    pub shields_broken: u32,
+    pub criticals: u32,
+    pub fumbles: u32,
+}
+
+/// Tallies each side's attacks from a combat log; `sides` gives each combatant's side.
+pub fn fight_stats(log: &[LogEntry], sides: &[usize]) -> [FightStats; 2] {
+    let mut stats = [FightStats::default(); 2];
+    for event in log.iter().flat_map(|entry| &entry.events) {
+        let Some(roll) = event.attack_roll() else {
+            continue;
+        };
+        let side = &mut stats[sides[roll.attacker]];
+        side.attacks += 1;
+        match event {
+            CombatEvent::Hit {
+                damage, critical, ..
+            } => {
+                side.hits += 1;
+                side.damage_dealt += damage.damage;
+                if critical.is_some() {
+                    side.criticals += 1;
+                }
+            }
+            CombatEvent::Miss {
+                fumble: Some(_), ..
+            } => side.fumbles += 1,
+            CombatEvent::ShieldBlock {
+                shield_damage,
+                shield_broken,
+                ..
+            } => {
+                side.shield_blocks += 1;
+                // Whatever gets through the shield and armor still costs hit points.
+                side.damage_dealt += shield_damage.damage;
+                if *shield_broken {
+                    side.shields_broken += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    stats
+}
+
 #[derive(Clone, Debug)]
 pub struct SimState {
     pub config: SimConfig,
-    pub actors: [SimActor; 2],
-    pub combatants: [Combatant; 2],
+    /// One per combatant, in the same order.
+    pub actors: Vec<SimActor>,
+    pub combatants: Vec<Combatant>,
+    /// Walls, cover and difficult ground, in the same feet as the actors' positions.
+    pub arena: Arena,
     pub elapsed_seconds: u32,
     pub done: bool,
-    pub last_event: Option<String>,
-    pub combat_log: Vec<String>,
+    pub combat_log: Vec<LogEntry>,
+    pub critical_table: CriticalTable,
     rng: rand::rngs::StdRng,
     tick_accum: f32,
 }
@@ -325,41 +1308,160 @@ impl SimState {
     pub fn new(config: SimConfig) -> Self {
         Self {
             config,
-            actors: [
-                SimActor { position: 0.0 },
+            actors: vec![
+                SimActor {
+                    position: Point::new(0.0, 0.0),
+                    facing: Point::new(1.0, 0.0),
+                    side: 0,
+                    target: None,
+                },
                 SimActor {
-                    position: config.start_distance,
+                    position: Point::new(config.start_distance, 0.0),
+                    facing: Point::new(-1.0, 0.0),
+                    side: 1,
+                    target: None,
                 },
             ],
-            combatants: [Combatant::default(), Combatant::default()],
+            combatants: vec![Combatant::default(), Combatant::default()],
+            arena: Arena::default(),
             elapsed_seconds: 0,
             done: false,
-            last_event: None,
             combat_log: Vec::new(),
-            rng: rand::rngs::StdRng::seed_from_u64(1),
+            critical_table: CriticalTable::default(),
+            rng: rand::rngs::StdRng::seed_from_u64(config.seed),
             tick_accum: 0.0,
         }
     }
 
+    pub fn with_arena(mut self, arena: Arena) -> Self {
+        self.arena = arena;
+        self
+    }
+
+    /// Rolls criticals and fumbles on `critical_table` instead of the built-in one.
+    pub fn with_critical_table(mut self, critical_table: CriticalTable) -> Self {
+        self.critical_table = critical_table;
+        self
+    }
+
     pub fn reset(&mut self) {
-        self.actors[0].position = 0.0;
-        self.actors[1].position = self.config.start_distance;
+        let rank_sizes = [0, 1].map(|side| {
+            self.actors
+                .iter()
+                .filter(|actor| actor.side == side)
+                .count()
+        });
+        let mut ranks = [0; 2];
+        for actor in &mut self.actors {
+            // Each side stands in a rank across the field, centered on the x axis.
+            let rank = ranks[actor.side];
+            ranks[actor.side] += 1;
+            let y = (rank as f32 - (rank_sizes[actor.side] - 1) as f32 / 2.0) * RANK_SPACING_FEET;
+            (actor.position, actor.facing) = if actor.side == 0 {
+                (Point::new(0.0, y), Point::new(1.0, 0.0))
+            } else {
+                (
+                    Point::new(self.config.start_distance, y),
+                    Point::new(-1.0, 0.0),
+                )
+            };
+            actor.target = None;
+        }
         self.elapsed_seconds = 0;
         self.done = false;
-        self.last_event = None;
         self.combat_log.clear();
-        self.rng = rand::rngs::StdRng::seed_from_u64(1);
+        self.rng = rand::rngs::StdRng::seed_from_u64(self.config.seed);
         for combatant in &mut self.combatants {
             combatant.reset_hp();
         }
         self.tick_accum = 0.0;
+        self.roll_initiative();
+    }
+
+    fn roll_initiative(&mut self) {
+        let mut events = vec![CombatEvent::Seed {
+            seed: self.config.seed,
+        }];
+        for idx in 0..self.combatants.len() {
+            let offset = self.config.initiative_offset(self.actors[idx].side);
+            let combatant = &mut self.combatants[idx];
+            let die = penetrating_roll(combatant.initiative_die_sides, &mut self.rng);
+            let count = (die + combatant.initiative_mod).max(1) as f32 + offset;
+            combatant.initiative_count = Some(count);
+            combatant.next_attack_time = Some(count);
+            events.push(CombatEvent::Initiative {
+                combatant: idx,
+                name: combatant.name.clone(),
+                die_sides: combatant.initiative_die_sides,
+                die,
+                modifier: combatant.initiative_mod,
+                count,
+            });
+        }
+        self.combat_log.push(LogEntry { time: 0, events });
+    }
+
+    pub fn last_entry(&self) -> Option<&LogEntry> {
+        self.combat_log.last()
     }
 
     pub fn reset_with_combatants(&mut self, combatants: [Combatant; 2]) {
-        self.combatants = combatants;
+        let [first, second] = combatants;
+        self.reset_with_sides([vec![first], vec![second]]);
+    }
+
+    /// Replaces the fighters with two sides of any size and resets. Side 0 lines up at
+    /// `x = 0`, side 1 at `start_distance`; a duel is one fighter a side.
+    pub fn reset_with_sides(&mut self, sides: [Vec<Combatant>; 2]) {
+        self.actors = sides
+            .iter()
+            .enumerate()
+            .flat_map(|(side, fighters)| {
+                fighters.iter().map(move |_| SimActor {
+                    position: Point::default(),
+                    facing: Point::default(),
+                    side,
+                    target: None,
+                })
+            })
+            .collect();
+        self.combatants = sides.into_iter().flatten().collect();
+        self.reset();
+    }
+
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.config.seed = seed;
         self.reset();
     }
 
+    pub fn seed(&self) -> u64 {
+        self.config.seed
+    }
+
+    /// Each combatant's side, by index.
+    pub fn sides(&self) -> Vec<usize> {
+        self.actors.iter().map(|actor| actor.side).collect()
+    }
+
+    fn side_down(&self, side: usize) -> bool {
+        self.actors
+            .iter()
+            .zip(&self.combatants)
+            .all(|(actor, combatant)| actor.side != side || combatant.hp <= 0)
+    }
+
+    /// The side still standing once the fight is over; in a duel, the winner's index.
+    pub fn winner(&self) -> Option<usize> {
+        if !self.done {
+            return None;
+        }
+        match (self.side_down(0), self.side_down(1)) {
+            (false, true) => Some(0),
+            (true, false) => Some(1),
+            _ => None,
+        }
+    }
+
     pub fn update(&mut self, dt: f32) {
         if self.done {
             return;
@@ -378,85 +1480,403 @@ impl SimState {
         if self.done {
             return;
         }
-        let distance = self.distance();
-        let old_positions = [self.actors[0].position, self.actors[1].position];
-        let step_a = self.combatants[0].move_speed.max(0.0);
-        let step_b = self.combatants[1].move_speed.max(0.0);
-        let reach_a = self.combatants[0].reach_ft.max(1.0);
-        let reach_b = self.combatants[1].reach_ft.max(1.0);
-        let max_reach = self.config.stop_distance.max(1.0);
-        let min_reach = reach_a.min(reach_b);
-        let ranged_a = max_range_for_weapon(&self.combatants[0].weapon_name).is_some();
-        let ranged_b = max_range_for_weapon(&self.combatants[1].weapon_name).is_some();
-        let any_ranged = ranged_a || ranged_b;
-
-        if distance > max_reach && !any_ranged {
-            self.actors[0].position += step_a;
-            self.actors[1].position -= step_b;
-            for combatant in &mut self.combatants {
-                combatant.next_attack_time = None;
+        let now = self.elapsed_seconds as f32;
+        let mut events = Vec::new();
+        for (idx, combatant) in self.combatants.iter_mut().enumerate() {
+            for status in combatant.statuses.expire(now) {
+                events.push(CombatEvent::StatusEnded {
+                    combatant: idx,
+                    name: combatant.name.clone(),
+                    status,
+                });
+            }
+        }
+        self.choose_targets();
+        events.extend(self.release_held());
+        let turning = self.turn_to_targets();
+        for idx in (0..turning.len()).filter(|&idx| turning[idx]) {
+            let combatant = &mut self.combatants[idx];
+            // The turn costs the second: no step, and an attack due now waits for it.
+            combatant.next_attack_time = combatant
+                .next_attack_time
+                .map(|time| time.max(now + TURN_SECONDS));
+            events.push(CombatEvent::Turn {
+                combatant: idx,
+                name: combatant.name.clone(),
+            });
+        }
+        let intents = self.decide_intents();
+        for (idx, intent) in intents.iter().enumerate() {
+            let combatant = &mut self.combatants[idx];
+            if intent.two_hand_grip && combatant.take_two_hand_grip() {
+                events.push(CombatEvent::TwoHandGrip {
+                    combatant: idx,
+                    name: combatant.name.clone(),
+                    weapon_name: combatant.weapon_name.clone(),
+                });
             }
-        } else {
-            self.resolve_combat_round();
-            let distance = self.distance();
-            if any_ranged {
-                let backstep = 5.0;
-                let engaged = distance <= min_reach;
-                if !engaged {
-                    if ranged_a {
-                        if let Some(max_range) =
-                            max_range_for_weapon(&self.combatants[0].weapon_name)
-                        {
-                            if distance <= max_range {
-                                self.actors[0].position -= backstep;
-                            } else {
-                                self.actors[0].position += step_a;
-                            }
-                        }
-                    } else if distance > reach_a {
-                        self.actors[0].position += step_a;
-                    }
-                    if ranged_b {
-                        if let Some(max_range) =
-                            max_range_for_weapon(&self.combatants[1].weapon_name)
-                        {
-                            if distance <= max_range {
-                                self.actors[1].position += backstep;
-                            } else {
-                                self.actors[1].position -= step_b;
-                            }
-                        }
-                    } else if distance > reach_b {
-                        self.actors[1].position -= step_b;
-                    }
-                }
-            } else if distance > min_reach {
-                if reach_a < reach_b {
-                    self.actors[0].position += step_a;
-                } else if reach_b < reach_a {
-                    self.actors[1].position -= step_b;
-                }
+        }
+        // Fighters short of engagement distance close in without attacking; everyone
+        // else fights their target.
+        let engage_distance = self.config.stop_distance.max(1.0);
+        let closing: Vec<bool> = (0..self.combatants.len())
+            .map(|idx| !turning[idx] && self.closing(idx, engage_distance))
+            .collect();
+        let engaged: Vec<bool> = (0..self.combatants.len())
+            .map(|idx| {
+                !closing[idx]
+                    && !turning[idx]
+                    && self.combatants[idx].hp > 0
+                    && self.actors[idx].target.is_some()
+            })
+            .collect();
+
+        events.extend(self.resolve_combat_round(&engaged));
+        // Where each fighter stands before moving; knockback lands here, not as a move.
+        let held_positions: Vec<Point> = self.actors.iter().map(|actor| actor.position).collect();
+        // Closing fighters all set off for where their target stood, and may overrun it
+        // until `stop_at_engagement` settles the meeting.
+        let lines: Vec<Point> = (0..closing.len())
+            .map(|idx| {
+                self.actors[idx]
+                    .target
+                    .map_or(self.actors[idx].facing, |target| self.heading(idx, target))
+            })
+            .collect();
+        for idx in (0..closing.len()).filter(|&idx| closing[idx]) {
+            self.step(idx, intents[idx].movement, lines[idx], f32::INFINITY);
+        }
+        self.stop_at_engagement(&closing, &held_positions, engage_distance);
+        for idx in (0..closing.len()).filter(|&idx| closing[idx]) {
+            self.combatants[idx].next_attack_time = None;
+        }
+        for idx in (0..engaged.len()).filter(|&idx| engaged[idx]) {
+            let Some(target) = self.actors[idx].target else {
+                continue;
+            };
+            // Advancing stops once the target is within reach and not behind a wall.
+            if self.combatants[idx].hp > 0
+                && (intents[idx].movement != Movement::Advance
+                    || self.distance_between(idx, target) > self.combatants[idx].reach_ft.max(1.0)
+                    || !self.line_of_fire(idx, target))
+            {
+                let toward = self.heading(idx, target);
+                let distance = self.distance_between(idx, target);
+                self.step(idx, intents[idx].movement, toward, distance);
             }
         }
         for (idx, combatant) in self.combatants.iter_mut().enumerate() {
-            combatant.moved_last_tick =
-                (self.actors[idx].position - old_positions[idx]).abs() > f32::EPSILON;
+            let new_position = self.actors[idx].position;
+            let step = new_position - held_positions[idx];
+            combatant.moved_last_tick = step.length() > f32::EPSILON;
+            let toward = self.actors[idx].target.map_or(Point::default(), |target| {
+                held_positions[target] - held_positions[idx]
+            });
+            if step.dot(toward) > 0.0 {
+                combatant.run_feet += step.length();
+            } else {
+                combatant.run_feet = 0.0;
+            }
+            if combatant.moved_last_tick {
+                combatant.braced_seconds = 0.0;
+            } else {
+                combatant.braced_seconds += 1.0;
+            }
+            if combatant.moved_last_tick {
+                events.push(CombatEvent::Move {
+                    combatant: idx,
+                    name: combatant.name.clone(),
+                    from: held_positions[idx],
+                    to: new_position,
+                });
+            }
+        }
+        if !events.is_empty() {
+            self.combat_log.push(LogEntry {
+                time: self.elapsed_seconds,
+                events,
+            });
+        }
+        // Leave the clock on the second of the deciding blow.
+        if !self.done {
+            self.elapsed_seconds += 1;
         }
-        self.elapsed_seconds += 1;
     }
 
+    /// Distance between the first two fighters, which in a duel is the whole fight.
     pub fn distance(&self) -> f32 {
-        (self.actors[1].position - self.actors[0].position).max(0.0)
+        self.distance_between(0, 1)
     }
 
-    fn resolve_combat_round(&mut self) {
-        let now = self.elapsed_seconds as f32;
-        let distance = self.distance();
-        let mut events = Vec::new();
-        for (attacker_idx, defender_idx) in [(0usize, 1usize), (1usize, 0usize)] {
-            if self.combatants[attacker_idx].hp <= 0 || self.combatants[defender_idx].hp <= 0 {
-                continue;
-            }
+    pub fn distance_between(&self, from: usize, to: usize) -> f32 {
+        self.actors[from]
+            .position
+            .distance(self.actors[to].position)
+    }
+
+    /// Unit vector from one fighter toward another, or the first one's facing if they
+    /// stand on the same spot.
+    fn direction_to(&self, from: usize, to: usize) -> Point {
+        self.actors[from]
+            .position
+            .direction_to(self.actors[to].position)
+            .unwrap_or(self.actors[from].facing)
+    }
+
+    /// Unit vector along which a fighter walks toward another, around any wall between.
+    fn heading(&self, from: usize, to: usize) -> Point {
+        self.arena
+            .heading(self.actors[from].position, self.actors[to].position)
+            .unwrap_or(self.actors[from].facing)
+    }
+
+    fn line_of_fire(&self, from: usize, to: usize) -> bool {
+        self.arena
+            .line_of_fire(self.actors[from].position, self.actors[to].position)
+    }
+
+    /// Moves a fighter by `offset`, stopping short of any wall in the way.
+    fn shift(&mut self, idx: usize, offset: Point) {
+        let position = self.actors[idx].position;
+        self.actors[idx].position = self.arena.walk(position, position + offset);
+    }
+
+    /// Faces each standing fighter toward their target. A target inside the front arc is
+    /// followed for free; any further round takes a second, and the fighters returned do
+    /// nothing else this second.
+    fn turn_to_targets(&mut self) -> Vec<bool> {
+        let mut turning = vec![false; self.actors.len()];
+        for (idx, turns) in turning.iter_mut().enumerate() {
+            let Some(target) = self.actors[idx].target else {
+                continue;
+            };
+            let modifiers = self.combatants[idx].statuses.modifiers();
+            if self.combatants[idx].hp <= 0 || !modifiers.can_act || modifiers.movement <= 0.0 {
+                continue;
+            }
+            let actor = &self.actors[idx];
+            let Some(toward) = actor.position.direction_to(self.actors[target].position) else {
+                continue;
+            };
+            *turns = actor.facing.dot(toward) < FRONT_ARC_COS;
+            self.actors[idx].facing = toward;
+        }
+        turning
+    }
+
+    /// Which way the attacker comes at the defender, from the way the defender faces.
+    fn attack_angle(&self, attacker: usize, defender: usize) -> AttackAngle {
+        let defender = &self.actors[defender];
+        let Some(toward) = defender
+            .position
+            .direction_to(self.actors[attacker].position)
+        else {
+            return AttackAngle::Front;
+        };
+        let alignment = defender.facing.dot(toward);
+        if alignment >= FRONT_ARC_COS {
+            AttackAngle::Front
+        } else if alignment > REAR_ARC_COS {
+            AttackAngle::Flank
+        } else {
+            AttackAngle::Rear
+        }
+    }
+
+    /// Places the attacker among the living melee fighters going after the defender
+    /// within their reach. The defender's own target comes first, then by index.
+    fn attack_position(&self, attacker: usize, defender: usize) -> AttackPosition {
+        let mut attackers: Vec<usize> = (0..self.combatants.len())
+            .filter(|&idx| {
+                let combatant = &self.combatants[idx];
+                (idx == attacker
+                    || self.actors[idx].target == Some(defender)
+                        && combatant.hp > 0
+                        && max_range_for_weapon(&combatant.weapon_name).is_none()
+                        && self.distance_between(idx, defender) <= combatant.reach_ft.max(1.0))
+                    && self.actors[idx].side != self.actors[defender].side
+            })
+            .collect();
+        attackers.sort_by_key(|&idx| (self.actors[defender].target != Some(idx), idx));
+        let total = attackers.len();
+        attackers.retain(|&idx| self.attack_angle(idx, defender) != AttackAngle::Rear);
+        AttackPosition {
+            angle: self.attack_angle(attacker, defender),
+            order: attackers
+                .iter()
+                .position(|&idx| idx == attacker)
+                .map_or(1, |order| order + 1),
+            attackers: total,
+        }
+    }
+
+    /// Keeps each fighter on a living enemy, choosing a new one by their target priority
+    /// once the old one falls. Ties go to the enemy the fewest allies are already after.
+    fn choose_targets(&mut self) {
+        for idx in 0..self.combatants.len() {
+            let side = self.actors[idx].side;
+            if self.combatants[idx].hp <= 0
+                || self.actors[idx]
+                    .target
+                    .is_some_and(|target| self.combatants[target].hp > 0)
+            {
+                continue;
+            }
+            let priority = self.combatants[idx].target_priority;
+            let key = |enemy: usize| {
+                let score = match priority {
+                    TargetPriority::Nearest => self.distance_between(idx, enemy),
+                    TargetPriority::Weakest => self.combatants[enemy].hp as f32,
+                    TargetPriority::Strongest => -(self.combatants[enemy].hp as f32),
+                };
+                let pursuers = self
+                    .actors
+                    .iter()
+                    .zip(&self.combatants)
+                    .filter(|(actor, ally)| {
+                        actor.side == side && ally.hp > 0 && actor.target == Some(enemy)
+                    })
+                    .count();
+                (score, pursuers)
+            };
+            self.actors[idx].target = (0..self.combatants.len())
+                .filter(|&enemy| self.actors[enemy].side != side && self.combatants[enemy].hp > 0)
+                .min_by(|&a, &b| {
+                    let (score_a, pursuers_a) = key(a);
+                    let (score_b, pursuers_b) = key(b);
+                    score_a
+                        .total_cmp(&score_b)
+                        .then(pursuers_a.cmp(&pursuers_b))
+                        .then(a.cmp(&b))
+                });
+        }
+    }
+
+    /// Whether the fighter is still walking up to their target. Ranged weapons are in the
+    /// fight from the start.
+    fn closing(&self, idx: usize, engage_distance: f32) -> bool {
+        let Some(target) = self.actors[idx].target else {
+            return false;
+        };
+        self.combatants[idx].hp > 0
+            && self.distance_between(idx, target) > engage_distance
+            && max_range_for_weapon(&self.combatants[idx].weapon_name).is_none()
+            && max_range_for_weapon(&self.combatants[target].weapon_name).is_none()
+    }
+
+    /// After a closing step, the longer weapon meets its opponent at engagement distance;
+    /// whoever closed the last of the gap stops there. Otherwise fighters who ran past
+    /// each other are brought back to meet.
+    fn stop_at_engagement(&mut self, closing: &[bool], old_positions: &[Point], engage: f32) {
+        for idx in (0..closing.len()).filter(|&idx| closing[idx]) {
+            let Some(target) = self.actors[idx].target else {
+                continue;
+            };
+            if closing[target] && self.actors[target].target == Some(idx) && target < idx {
+                // Already settled from the other side of the pair.
+                continue;
+            }
+            // Measured along the line between them, so a pass shows as a negative gap. There
+            // is no meeting through a wall.
+            let Some(line) = old_positions[idx].direction_to(old_positions[target]) else {
+                continue;
+            };
+            if !self
+                .arena
+                .line_of_fire(old_positions[idx], old_positions[target])
+            {
+                continue;
+            }
+            let reach = self.combatants[idx].reach_ft.max(1.0);
+            let target_reach = self.combatants[target].reach_ft.max(1.0);
+            let gap = (self.actors[target].position - self.actors[idx].position).dot(line);
+            let overshoot = engage - gap;
+            if overshoot <= 0.0 {
+                continue;
+            }
+            let moved = self.actors[idx].position != old_positions[idx];
+            let target_moved = self.actors[target].position != old_positions[target];
+            if self.config.rules.hold_at_bay && reach != target_reach {
+                if (reach < target_reach && moved) || (target_reach < reach && !target_moved) {
+                    self.shift(idx, -line * overshoot);
+                } else {
+                    self.shift(target, line * overshoot);
+                }
+            } else if gap < 0.0 {
+                let share = if moved && target_moved { 0.5 } else { 1.0 };
+                if moved {
+                    self.shift(idx, line * (gap * share));
+                }
+                if target_moved {
+                    self.shift(target, line * (-gap * share));
+                }
+            }
+        }
+    }
+
+    /// Asks each fighter's tactics what they mean to do this second.
+    fn decide_intents(&mut self) -> Vec<Intent> {
+        let now = self.elapsed_seconds as f32;
+        let intents: Vec<Intent> = (0..self.combatants.len())
+            .map(|idx| {
+                let me = &self.combatants[idx];
+                match self.actors[idx].target {
+                    Some(target) if me.hp > 0 => me.tactics.decide(&Situation {
+                        me,
+                        opponent: &self.combatants[target],
+                        distance: self.distance_between(idx, target),
+                        now,
+                    }),
+                    _ => Intent::configured(me, Movement::Hold),
+                }
+            })
+            .collect();
+        for (combatant, intent) in self.combatants.iter_mut().zip(&intents) {
+            combatant.intent = Some(*intent);
+        }
+        intents
+    }
+
+    /// Moves a fighter along `toward`, the way to their target: up to a full move in, or a
+    /// walking step back, slowed or stopped by their statuses and the ground underfoot.
+    fn step(&mut self, idx: usize, movement: Movement, toward: Point, max_advance: f32) {
+        let combatant = &self.combatants[idx];
+        let feet = match movement {
+            Movement::Advance => combatant.move_speed.max(0.0).min(max_advance),
+            Movement::Hold => 0.0,
+            Movement::Retreat => -RETREAT_STEP_FEET,
+        } * combatant.statuses.modifiers().movement
+            * self.arena.movement_at(self.actors[idx].position);
+        self.shift(idx, toward * feet);
+    }
+
+    /// Unit vector leading from one fighter directly away from the other.
+    fn away_from(&self, from_idx: usize, idx: usize) -> Point {
+        self.direction_to(from_idx, idx)
+    }
+
+    /// Charges, holds at bay and attacks for every engaged fighter against their target,
+    /// measured from where everyone stood at the start of the second.
+    fn resolve_combat_round(&mut self, engaged: &[bool]) -> Vec<CombatEvent> {
+        let now = self.elapsed_seconds as f32;
+        let pairs: Vec<(usize, usize, f32)> = (0..engaged.len())
+            .filter(|&idx| engaged[idx])
+            .filter_map(|idx| {
+                let target = self.actors[idx].target?;
+                // Nobody strikes or shoots through a wall.
+                self.line_of_fire(idx, target)
+                    .then(|| (idx, target, self.distance_between(idx, target)))
+            })
+            .collect();
+        let mut events = self.resolve_charges(&pairs);
+        if self.done {
+            return events;
+        }
+        events.extend(self.hold_at_bay(&pairs));
+        for &(attacker_idx, defender_idx, distance) in &pairs {
+            if self.combatants[attacker_idx].hp <= 0 || self.combatants[defender_idx].hp <= 0 {
+                continue;
+            }
             let weapon_name = &self.combatants[attacker_idx].weapon_name;
             let is_ranged_weapon = max_range_for_weapon(weapon_name).is_some();
             let ranged_mod = if is_ranged_weapon {
@@ -464,59 +1884,656 @@ impl SimState {
             } else {
                 None
             };
+            if self.combatants[attacker_idx]
+                .statuses
+                .has(StatusKind::HeldAtBay)
+            {
+                events.extend(self.knock_aside(attacker_idx, defender_idx));
+                continue;
+            }
             if !is_ranged_weapon && distance > self.combatants[attacker_idx].reach_ft.max(1.0) {
                 continue;
             }
             if is_ranged_weapon && ranged_mod.is_none() {
                 continue;
             }
+            if !self.combatants[attacker_idx].statuses.modifiers().can_act {
+                // The lost seconds are not made up once the fighter recovers.
+                let attacker = &mut self.combatants[attacker_idx];
+                attacker.next_attack_time =
+                    attacker.next_attack_time.map(|time| time.max(now + 1.0));
+                continue;
+            }
             if self.combatants[attacker_idx].next_attack_time.is_none() {
                 let attacker_reach = self.combatants[attacker_idx].reach_ft;
                 let defender_reach = self.combatants[defender_idx].reach_ft;
-                let delay = if !is_ranged_weapon && attacker_reach < defender_reach {
+                let delay = if self.config.rules.reach_delay
+                    && !is_ranged_weapon
+                    && attacker_reach < defender_reach
+                {
                     1.0
                 } else {
                     0.0
                 };
-                self.combatants[attacker_idx].next_attack_time = Some(now + delay);
+                let initiative = self.combatants[attacker_idx]
+                    .initiative_count
+                    .unwrap_or(now);
+                self.combatants[attacker_idx].next_attack_time =
+                    Some((now + delay).max(initiative));
             }
             let next_attack = self.combatants[attacker_idx]
                 .next_attack_time
                 .unwrap_or(now);
-            if now + 0.0001 >= next_attack {
-                let event = resolve_attack(
-                    &mut self.combatants,
-                    attacker_idx,
-                    defender_idx,
-                    ranged_mod.unwrap_or(0),
-                    is_ranged_weapon,
-                    &mut self.rng,
-                );
-                events.push(event);
-                let speed = self.combatants[attacker_idx].weapon_speed.max(1.0);
-                self.combatants[attacker_idx].next_attack_time = Some(next_attack + speed);
-                if self.combatants[defender_idx].hp <= 0 {
-                    self.done = true;
-                    break;
+            if now + 0.0001 < next_attack {
+                continue;
+            }
+            let halved = !is_ranged_weapon
+                && self.combatants[defender_idx]
+                    .current_style()
+                    .halves_opponent_speed();
+            let target_held = !is_ranged_weapon
+                && self.combatants[defender_idx]
+                    .statuses
+                    .has(StatusKind::HeldAtBay);
+            let attacker = &mut self.combatants[attacker_idx];
+            match attacker.intended_action() {
+                TurnAction::FullParry => {
+                    let speed =
+                        (attacker.weapon_speed + attacker.statuses.modifiers().speed).max(1.0);
+                    attacker.next_attack_time = Some(next_attack + speed);
+                    attacker
+                        .statuses
+                        .apply(StatusKind::FullParry, next_attack + speed);
+                    events.push(CombatEvent::FullParry {
+                        combatant: attacker_idx,
+                        name: attacker.name.clone(),
+                    });
+                    continue;
+                }
+                // A weapon drawn in melee must wait out its speed before it can attack.
+                TurnAction::SwapWeapon if attacker.swap_weapon() => {
+                    let ready_at = next_attack
+                        + attacker.draw_seconds
+                        + (attacker.weapon_speed + attacker.statuses.modifiers().speed).max(1.0);
+                    attacker.next_attack_time = Some(ready_at);
+                    events.push(CombatEvent::WeaponSwap {
+                        combatant: attacker_idx,
+                        name: attacker.name.clone(),
+                        weapon_name: attacker.weapon_name.clone(),
+                        ready_at,
+                    });
+                    continue;
                 }
+                _ => {}
+            }
+            let style = attacker.current_style();
+            let speed = (attacker.attack_speed(target_held)
+                + style.speed_mod()
+                + attacker.statuses.modifiers().speed)
+                .max(1.0);
+            attacker.next_attack_time = Some(
+                next_attack
+                    + if halved {
+                        (speed / 2.0).max(1.0)
+                    } else {
+                        speed
+                    },
+            );
+            if style == FightingStyle::TwoWeaponsOffensive && attacker.next_off_hand_time.is_none()
+            {
+                attacker.next_off_hand_time =
+                    Some(next_attack + OFF_HAND_DELAY_SECONDS + attacker.weapon_speed / 2.0);
+            }
+            events.extend(self.strike(
+                attacker_idx,
+                defender_idx,
+                ranged_mod.unwrap_or(0),
+                is_ranged_weapon,
+            ));
+            if self.done {
+                return events;
             }
         }
-        if !events.is_empty() {
-            let line = format!("t={}s | {}", self.elapsed_seconds, events.join(" | "));
-            self.last_event = Some(line.clone());
-            self.combat_log.push(line);
+        events.extend(self.off_hand_attacks(&pairs));
+        events
+    }
+
+    /// Second-weapon attacks in the offensive two-weapon style, on their own clock once the
+    /// first primary attack has set it going.
+    fn off_hand_attacks(&mut self, pairs: &[(usize, usize, f32)]) -> Vec<CombatEvent> {
+        let now = self.elapsed_seconds as f32;
+        let mut events = Vec::new();
+        for &(attacker_idx, defender_idx, distance) in pairs {
+            let attacker = &self.combatants[attacker_idx];
+            let Some(off_hand) = &attacker.off_hand else {
+                continue;
+            };
+            let due = attacker
+                .next_off_hand_time
+                .is_some_and(|time| now + 0.0001 >= time);
+            // The off-hand weapon strikes whenever the primary one is engaged.
+            if !due
+                || attacker.current_style() != FightingStyle::TwoWeaponsOffensive
+                || attacker.hp <= 0
+                || self.combatants[defender_idx].hp <= 0
+                || attacker.statuses.has(StatusKind::HeldAtBay)
+                || distance > attacker.reach_ft.max(1.0)
+            {
+                continue;
+            }
+            if !attacker.statuses.modifiers().can_act {
+                let attacker = &mut self.combatants[attacker_idx];
+                attacker.next_off_hand_time =
+                    attacker.next_off_hand_time.map(|time| time.max(now + 1.0));
+                continue;
+            }
+            let halved = self.combatants[defender_idx]
+                .current_style()
+                .halves_opponent_speed();
+            let speed = (off_hand.weapon_speed
+                + FightingStyle::TwoWeaponsOffensive.speed_mod()
+                + attacker.statuses.modifiers().speed)
+                .max(1.0);
+            let attacker = &mut self.combatants[attacker_idx];
+            attacker.next_off_hand_time = attacker.next_off_hand_time.map(|time| {
+                time + if halved {
+                    (speed / 2.0).max(1.0)
+                } else {
+                    speed
+                }
+            });
+            events.extend(self.off_hand_strike(attacker_idx, defender_idx));
+            if self.done {
+                break;
+            }
+        }
+        events
+    }
+
+    /// Strikes with the off-hand weapon, minus its damage penalty. The primary weapon
+    /// waits in the off-hand slot meanwhile, so the style holds throughout.
+    fn off_hand_strike(&mut self, attacker_idx: usize, defender_idx: usize) -> Vec<CombatEvent> {
+        let attacker = &mut self.combatants[attacker_idx];
+        let Some(mut loadout) = attacker.off_hand.take() else {
+            return Vec::new();
+        };
+        attacker.exchange_loadout(&mut loadout);
+        attacker.strength_damage -= OFF_HAND_DAMAGE_PENALTY;
+        attacker.off_hand = Some(loadout);
+        let events = self.strike(attacker_idx, defender_idx, 0, false);
+        let attacker = &mut self.combatants[attacker_idx];
+        if let Some(mut loadout) = attacker.off_hand.take() {
+            attacker.strength_damage += OFF_HAND_DAMAGE_PENALTY;
+            attacker.exchange_loadout(&mut loadout);
+            attacker.off_hand = Some(loadout);
+        }
+        events
+    }
+
+    /// Declares a charge for each fighter making contact after a long enough straight run.
+    /// A defender braced with a set weapon strikes the charger first; a hit breaks the
+    /// charge and holds the charger at bay.
+    fn resolve_charges(&mut self, pairs: &[(usize, usize, f32)]) -> Vec<CombatEvent> {
+        let now = self.elapsed_seconds as f32;
+        let mut events = Vec::new();
+        for &(charger_idx, defender_idx, distance) in pairs {
+            let charger = &self.combatants[charger_idx];
+            if !charger.intends_charge()
+                || charger.next_attack_time.is_some()
+                || charger.run_feet < CHARGE_RUN_FEET
+                || charger.hp <= 0
+                || self.combatants[defender_idx].hp <= 0
+                || max_range_for_weapon(&charger.weapon_name).is_some()
+                || distance > charger.reach_ft.max(1.0)
+            {
+                continue;
+            }
+            let charger = &mut self.combatants[charger_idx];
+            charger.charging = true;
+            charger
+                .statuses
+                .apply(StatusKind::Charging, now + CHARGE_EXPOSED_SECONDS);
+            events.push(CombatEvent::Charge {
+                combatant: charger_idx,
+                name: charger.name.clone(),
+                feet: charger.run_feet,
+            });
+            let defender = &mut self.combatants[defender_idx];
+            if !defender.set_for_charge
+                || defender.braced_seconds < SET_FOR_CHARGE_BRACE_SECONDS
+                || !defender.statuses.modifiers().can_act
+                || distance > defender.reach_ft.max(1.0)
+            {
+                continue;
+            }
+            let speed = (defender.weapon_speed + defender.statuses.modifiers().speed).max(1.0);
+            defender.set_strike = true;
+            defender.next_attack_time = Some(now + speed);
+            let strike = self.strike(defender_idx, charger_idx, 0, false);
+            let broken = matches!(strike.first(), Some(CombatEvent::Hit { .. }));
+            events.extend(strike);
+            if self.done {
+                break;
+            }
+            if broken {
+                let charger = &mut self.combatants[charger_idx];
+                charger.charging = false;
+                charger.next_attack_time = None;
+                charger.statuses.apply(StatusKind::HeldAtBay, f32::INFINITY);
+                charger.held_by = Some(defender_idx);
+                events.push(CombatEvent::ChargeBroken {
+                    combatant: charger_idx,
+                    name: charger.name.clone(),
+                });
+            }
+        }
+        events
+    }
+
+    /// Holds a shorter-reach fighter at bay as they try to step inside their target's
+    /// longer weapon.
+    fn hold_at_bay(&mut self, pairs: &[(usize, usize, f32)]) -> Vec<CombatEvent> {
+        let mut events = Vec::new();
+        if !self.config.rules.hold_at_bay {
+            return events;
+        }
+        for &(entering_idx, holder_idx, distance) in pairs {
+            let holder = &self.combatants[holder_idx];
+            let entering = &self.combatants[entering_idx];
+            if entering.reach_ft.max(1.0) >= holder.reach_ft.max(1.0)
+                || distance > holder.reach_ft.max(1.0)
+                || distance <= entering.reach_ft.max(1.0)
+                || holder.hp <= 0
+                || entering.hp <= 0
+                || !holder.statuses.modifiers().can_act
+                || holder.statuses.has(StatusKind::HeldAtBay)
+                || entering.statuses.has(StatusKind::HeldAtBay)
+                || max_range_for_weapon(&holder.weapon_name).is_some()
+                || max_range_for_weapon(&entering.weapon_name).is_some()
+            {
+                continue;
+            }
+            let holder = holder.name.clone();
+            let entering = &mut self.combatants[entering_idx];
+            entering
+                .statuses
+                .apply(StatusKind::HeldAtBay, f32::INFINITY);
+            entering.held_by = Some(holder_idx);
+            entering.next_attack_time = None;
+            events.push(CombatEvent::HeldAtBay {
+                combatant: entering_idx,
+                name: entering.name.clone(),
+                holder,
+            });
+        }
+        events
+    }
+
+    /// Frees held fighters whose holder is down, out of reach or no longer fighting them.
+    fn release_held(&mut self) -> Vec<CombatEvent> {
+        let mut events = Vec::new();
+        for held_idx in 0..self.combatants.len() {
+            let Some(holder_idx) = self.combatants[held_idx].held_by else {
+                continue;
+            };
+            let holder = &self.combatants[holder_idx];
+            if holder.hp > 0
+                && self.distance_between(held_idx, holder_idx) <= holder.reach_ft.max(1.0)
+                && self.actors[holder_idx].target == Some(held_idx)
+                && self.actors[held_idx].target == Some(holder_idx)
+            {
+                continue;
+            }
+            let held = &mut self.combatants[held_idx];
+            held.held_by = None;
+            if held.statuses.has(StatusKind::HeldAtBay) {
+                held.statuses.remove(StatusKind::HeldAtBay);
+                events.push(CombatEvent::StatusEnded {
+                    combatant: held_idx,
+                    name: held.name.clone(),
+                    status: StatusKind::HeldAtBay,
+                });
+            }
+        }
+        events
+    }
+
+    /// A held fighter spends their attack trying to knock the holder's weapon aside. On a
+    /// success they step inside their own reach and attack the following second.
+    fn knock_aside(&mut self, held_idx: usize, holder_idx: usize) -> Option<CombatEvent> {
+        let now = self.elapsed_seconds as f32;
+        let held = &mut self.combatants[held_idx];
+        if !held.statuses.modifiers().can_act {
+            return None;
+        }
+        let initiative = held.initiative_count.unwrap_or(now);
+        let due = *held.next_attack_time.get_or_insert(now.max(initiative));
+        if now + 0.0001 < due {
+            return None;
+        }
+        let attack_die = penetrating_roll(20, &mut self.rng);
+        let defense_die = penetrating_roll(20, &mut self.rng);
+        let held = &self.combatants[held_idx];
+        let holder = &self.combatants[holder_idx];
+        let attack_total = attack_die + held.attack_bonus + held.statuses.modifiers().attack;
+        let defense_total = defense_die
+            + holder.defense_mod
+            + holder.statuses.modifiers().defense
+            + holder.active_stance.defense_mod();
+        let success = attack_total >= defense_total;
+        let holder_name = holder.name.clone();
+        let held_reach = held.reach_ft.max(1.0);
+        let speed = (held.weapon_speed + held.statuses.modifiers().speed).max(1.0);
+        if success {
+            if self.distance_between(held_idx, holder_idx) > held_reach {
+                let away = self.away_from(holder_idx, held_idx);
+                self.actors[held_idx].position =
+                    self.actors[holder_idx].position + away * held_reach;
+            }
+            let held = &mut self.combatants[held_idx];
+            held.statuses.remove(StatusKind::HeldAtBay);
+            held.held_by = None;
+            held.next_attack_time = Some(now + 1.0);
+        } else {
+            self.combatants[held_idx].next_attack_time = Some(due + speed);
+        }
+        Some(CombatEvent::KnockAside {
+            combatant: held_idx,
+            name: self.combatants[held_idx].name.clone(),
+            holder: holder_name,
+            attack_die,
+            attack_total,
+            defense_die,
+            defense_total,
+            success,
+        })
+    }
+
+    /// Resolves one attack and everything that follows from it: criticals, fumbles,
+    /// retreats, broken shields, trauma saves, knockback and defeat.
+    fn strike(
+        &mut self,
+        attacker_idx: usize,
+        defender_idx: usize,
+        range_mod: i32,
+        is_ranged: bool,
+    ) -> Vec<CombatEvent> {
+        let now = self.elapsed_seconds as f32;
+        let mut events = Vec::new();
+        if self.config.rules.flanking {
+            self.combatants[defender_idx].attacked_from =
+                self.attack_position(attacker_idx, defender_idx);
+        }
+        if is_ranged {
+            self.combatants[defender_idx].behind_cover = self.arena.cover(
+                self.actors[attacker_idx].position,
+                self.actors[defender_idx].position,
+            );
+        }
+        let mut event = resolve_attack(
+            &mut self.combatants,
+            attacker_idx,
+            defender_idx,
+            range_mod,
+            is_ranged,
+            self.config.rules,
+            &mut self.rng,
+        );
+        let keeping_at_bay = event.attack_roll().is_some_and(|roll| roll.keeping_at_bay);
+        if self.config.rules.criticals && !keeping_at_bay {
+            apply_critical(
+                &mut event,
+                &mut self.combatants[defender_idx],
+                &self.critical_table,
+                now,
+                &mut self.rng,
+            );
+        }
+        if self.config.rules.fumbles {
+            apply_fumble(
+                &mut event,
+                &mut self.combatants[attacker_idx],
+                &self.critical_table,
+                now,
+                &mut self.rng,
+            );
+        }
+        if let Some(retreat) = event.attack_roll().and_then(|roll| roll.retreat) {
+            // The attacker steps in after the retreating defender.
+            let away = self.away_from(attacker_idx, defender_idx);
+            self.shift(defender_idx, away * retreat.feet());
+            if self.combatants[attacker_idx].statuses.modifiers().movement > 0.0 {
+                self.shift(attacker_idx, away * retreat.feet());
+            }
+        }
+        let shield_broken = matches!(
+            event,
+            CombatEvent::ShieldBlock {
+                shield_broken: true,
+                ..
+            }
+        );
+        let charge = event.attack_roll().is_some_and(|roll| roll.charge);
+        let hp_damage = match &event {
+            CombatEvent::Hit { damage, .. } => damage.damage,
+            CombatEvent::ShieldBlock { shield_damage, .. } => shield_damage.damage,
+            _ => 0,
+        };
+        events.push(event);
+        if shield_broken {
+            let defender = &self.combatants[defender_idx];
+            events.push(CombatEvent::ShieldBroken {
+                combatant: defender_idx,
+                name: defender.name.clone(),
+                shield_name: defender
+                    .shield_name
+                    .clone()
+                    .unwrap_or_else(|| "Shield".to_string()),
+            });
+        }
+        if self.config.rules.threshold_of_pain {
+            events.extend(check_threshold_of_pain(
+                &mut self.combatants[defender_idx],
+                defender_idx,
+                hp_damage,
+                now,
+                &mut self.rng,
+            ));
+        }
+        if self.config.rules.knockback {
+            let knockback = check_knockback(
+                &mut self.combatants[defender_idx],
+                defender_idx,
+                if charge { hp_damage * 2 } else { hp_damage },
+                now,
+                &mut self.rng,
+            );
+            if let Some(CombatEvent::Knockback { feet, .. }) = &knockback {
+                let away = self.away_from(attacker_idx, defender_idx);
+                self.shift(defender_idx, away * *feet);
+            }
+            events.extend(knockback);
         }
+        if self.combatants[defender_idx].hp <= 0 {
+            events.push(CombatEvent::Defeat {
+                combatant: defender_idx,
+                name: self.combatants[defender_idx].name.clone(),
+            });
+            self.done = self.side_down(self.actors[defender_idx].side);
+        }
+        events
+    }
+}
+
+/// Upgrades a hit scored with a natural 20 into a critical: extra damage past DR and
+/// any status from the band the attack's margin over the defense reaches.
+fn apply_critical(
+    event: &mut CombatEvent,
+    defender: &mut Combatant,
+    table: &CriticalTable,
+    now: f32,
+    rng: &mut impl Rng,
+) {
+    let CombatEvent::Hit {
+        roll,
+        damage,
+        hp_after,
+        critical,
+    } = event
+    else {
+        return;
+    };
+    if roll.attack_die < 20 {
+        return;
+    }
+    let severity = roll.attack_total - roll.defense_total;
+    let Some(effect) = table.critical_for(severity) else {
+        return;
+    };
+    let (extra_damage, detail) = roll_damage_expr_with_detail(&effect.extra_damage, rng);
+    let extra_damage = extra_damage.max(0);
+    defender.hp -= extra_damage;
+    damage.damage += extra_damage;
+    *hp_after = defender.hp;
+    if let Some(status) = effect.status.filter(|_| defender.hp > 0) {
+        defender.statuses.apply(status, now + effect.status_seconds);
+    }
+    *critical = Some(CriticalHit {
+        severity,
+        name: effect.name.clone(),
+        detail,
+        extra_damage,
+        status: effect.status,
+        status_seconds: effect.status_seconds,
+    });
+}
+
+/// Rolls on the fumble table after a natural 1 misses.
+fn apply_fumble(
+    event: &mut CombatEvent,
+    attacker: &mut Combatant,
+    table: &CriticalTable,
+    now: f32,
+    rng: &mut impl Rng,
+) {
+    let CombatEvent::Miss { roll, fumble } = event else {
+        return;
+    };
+    if roll.attack_die != 1 {
+        return;
+    }
+    let fumble_roll = standard_roll(20, rng);
+    let Some(effect) = table.fumble_for(fumble_roll) else {
+        return;
+    };
+    if let Some(next) = attacker.next_attack_time.as_mut() {
+        *next += effect.delay_seconds;
+    }
+    if let Some(status) = effect.status {
+        attacker.statuses.apply(status, now + effect.status_seconds);
     }
+    *fumble = Some(Fumble {
+        roll: fumble_roll,
+        name: effect.name.clone(),
+        delay_seconds: effect.delay_seconds,
+        status: effect.status,
+        status_seconds: effect.status_seconds,
+    });
+}
+
+/// Rolls the trauma save for a blow that exceeded the defender's threshold of pain.
+/// A failed save drops the defender for 5d4p seconds, during which it cannot act or move.
+fn check_threshold_of_pain(
+    defender: &mut Combatant,
+    defender_idx: usize,
+    damage: i32,
+    now: f32,
+    rng: &mut impl Rng,
+) -> Option<CombatEvent> {
+    let threshold = defender.threshold_of_pain?;
+    if damage <= threshold || defender.hp <= 0 || defender.statuses.has(StatusKind::Incapacitated) {
+        return None;
+    }
+    let save_roll = standard_roll(20, rng);
+    let incapacitated_seconds = if save_roll <= defender.trauma_save {
+        None
+    } else {
+        let (seconds, _) = evaluate_expression_with_detail(PAIN_INCAPACITATION_EXPR, rng);
+        let seconds = seconds.max(1);
+        defender
+            .statuses
+            .apply(StatusKind::Incapacitated, now + seconds as f32);
+        Some(seconds)
+    };
+    Some(CombatEvent::ThresholdOfPain {
+        combatant: defender_idx,
+        name: defender.name.clone(),
+        damage,
+        threshold,
+        save_roll,
+        save_target: defender.trauma_save,
+        incapacitated_seconds,
+    })
+}
+
+/// Knocks the defender back 5 ft per full 10 points of damage from a single blow.
+/// The defender then rolls d20p against the damage; rolling under it puts them prone,
+/// delaying their next action by the second it takes to stand.
+fn check_knockback(
+    defender: &mut Combatant,
+    defender_idx: usize,
+    damage: i32,
+    now: f32,
+    rng: &mut impl Rng,
+) -> Option<CombatEvent> {
+    if damage < KNOCKBACK_THRESHOLD || defender.hp <= 0 {
+        return None;
+    }
+    let feet = (damage / KNOCKBACK_THRESHOLD) as f32 * KNOCKBACK_FEET;
+    let balance_roll = penetrating_roll(20, rng);
+    let knocked_down = balance_roll < damage;
+    if knocked_down {
+        // Stand up on the next second, once any incapacitation has worn off.
+        let down_until = defender
+            .statuses
+            .until(StatusKind::Incapacitated)
+            .unwrap_or(now)
+            .max(now + 1.0);
+        let ready = down_until + STAND_UP_SECONDS;
+        defender.statuses.apply(StatusKind::Prone, ready);
+        defender.next_attack_time = Some(defender.next_attack_time.unwrap_or(ready).max(ready));
+    }
+    Some(CombatEvent::Knockback {
+        combatant: defender_idx,
+        name: defender.name.clone(),
+        damage,
+        feet,
+        balance_roll,
+        knocked_down,
+    })
+}
+
+/// The defender's retreat against this attack, if they have one and may take it: not
+/// while committed to an aggressive attack or a charge, not while pinned, and not from a
+/// faster mover.
+fn chosen_retreat(attacker: &Combatant, defender: &Combatant) -> Option<Retreat> {
+    defender.retreat.filter(|_| {
+        defender.active_stance != Stance::AggressiveAttack
+            && !defender.charging
+            && defender.statuses.modifiers().movement > 0.0
+            && attacker.move_speed <= defender.move_speed
+    })
 }
 
 fn resolve_attack(
-    combatants: &mut [Combatant; 2],
+    combatants: &mut [Combatant],
     attacker_idx: usize,
     defender_idx: usize,
     range_mod: i32,
     is_ranged: bool,
+    rules: RuleToggles,
     rng: &mut impl Rng,
-) -> String {
+) -> CombatEvent {
     let (
         attack_bonus,
         damage_expr,
@@ -524,7 +2541,6 @@ fn resolve_attack(
         strength_damage,
         weapon_name,
         armor_penetration,
-        use_jab,
         jab_special_expr,
     ) = {
         let attacker = &combatants[attacker_idx];
@@ -535,59 +2551,158 @@ fn resolve_attack(
             attacker.strength_damage,
             attacker.weapon_name.clone(),
             attacker.armor_penetration,
-            attacker.use_jab,
             attacker.jab_special_expr.clone(),
         )
     };
-    let shield_active = combatants[defender_idx].shield_intact;
-    let defense_mod = if is_ranged { 0 } else { combatants[defender_idx].defense_mod };
+    let stance = combatants[attacker_idx].intended_stance();
+    let charge = std::mem::take(&mut combatants[attacker_idx].charging) && !is_ranged;
+    let set_for_charge = std::mem::take(&mut combatants[attacker_idx].set_strike);
+    let damage_expr = if set_for_charge {
+        double_dice_expr(&damage_expr)
+    } else {
+        damage_expr
+    };
+    // Against a fighter held at bay, a weapon that can jab may only jab.
+    let target_held = !is_ranged && combatants[defender_idx].statuses.has(StatusKind::HeldAtBay);
+    let can_jab = combatants[attacker_idx].jab_speed.is_some();
+    let use_jab =
+        (combatants[attacker_idx].jabbing() || (can_jab && target_held)) && !set_for_charge;
+    let keeping_at_bay = target_held && !can_jab;
+    let attack_bonus = attack_bonus
+        + combatants[attacker_idx].statuses.modifiers().attack
+        + stance.attack_mod_after(combatants[attacker_idx].active_stance)
+        + std::mem::take(&mut combatants[attacker_idx].pending_attack_mod)
+        + if charge { CHARGE_ATTACK_BONUS } else { 0 };
+    // There is no backing away from a charge.
+    let retreat = if is_ranged || charge {
+        None
+    } else {
+        chosen_retreat(&combatants[attacker_idx], &combatants[defender_idx])
+    };
+    if let Some(retreat) = retreat {
+        combatants[defender_idx].pending_attack_mod += retreat.next_attack_mod();
+    }
+    let position = std::mem::take(&mut combatants[defender_idx].attacked_from);
+    let style = combatants[defender_idx].current_style();
+    let shield_active = combatants[defender_idx].shield_intact && position.shield_covers(style);
+    let defense_mod = if is_ranged {
+        0
+    } else if combatants[defender_idx].statuses.has(StatusKind::Charging) {
+        combatants[defender_idx].defense_mod - combatants[defender_idx].dex_defense.max(0)
+    } else {
+        combatants[defender_idx].defense_mod
+    } + combatants[defender_idx].statuses.modifiers().defense
+        + combatants[defender_idx].active_stance.defense_mod()
+        + retreat.map_or(0, Retreat::defense_bonus)
+        + style.defense_bonus();
     let armor_dr = combatants[defender_idx].armor_dr;
+    // Weapons alone defend at -4 unless the style allows the full die; a defensive
+    // off-hand weapon adds its own bonus on the same terms.
     let weapon_defense_bonus = if is_ranged {
         0
-    } else if combatants[defender_idx].weapon_defense_always
-        || (combatants[defender_idx].two_hand_grip
-            && combatants[defender_idx].defense_plus_four_ready)
-    {
-        4
     } else {
-        0
+        let defender = &combatants[defender_idx];
+        let window = style.full_weapon_defense(defender.defense_plus_four_ready);
+        let full = defender.weapon_defense_always || window;
+        let off_hand =
+            style == FightingStyle::TwoWeaponsDefensive && defender.off_hand.is_some() && window;
+        4 * (i32::from(full) + i32::from(off_hand))
     };
     let shield_defense_bonus = if shield_active {
         let base = if is_ranged { 0 } else { 4 };
-        base + combatants[defender_idx].shield_defense_bonus
+        let second_shield = if style == FightingStyle::TwoShields {
+            combatants[defender_idx].second_shield_defense_bonus
+        } else {
+            0
+        };
+        base + combatants[defender_idx].shield_defense_bonus + second_shield
     } else {
         0
     };
 
     let attack_die = penetrating_roll(20, rng);
-    let defense_die = penetrating_roll(
-        defense_die_sides(
+    let (defense_sides, defense_die_mod) = if is_ranged {
+        let sides = defense_die_sides(
             is_ranged,
             combatants[defender_idx].moved_last_tick,
             shield_active,
-        ),
-        rng,
-    );
+        );
+        (sides, 0)
+    } else {
+        match position.defense_die() {
+            (20, modifier) => (style.defense_die_sides(), modifier),
+            die => die,
+        }
+    };
+    let defense_die = penetrating_roll(defense_sides, rng);
     let mut attack_roll = attack_die + attack_bonus + range_mod;
-    if is_ranged && shield_active {
-        if let Some(cap) = combatants[defender_idx].shield_cover_value {
-            attack_roll = attack_roll.min(cap);
-        }
-    }
-    let defense_roll = defense_die + defense_mod + weapon_defense_bonus + shield_defense_bonus;
-    let mut damage = 0;
-    let mut hit = false;
-    let mut shield_block = false;
-    let mut damage_detail = "[0]".to_string();
-    let mut shield_damage_detail = "[0]".to_string();
-    let mut shield_damage = 0;
-    let mut shield_broken = false;
-
-    if attack_roll >= defense_roll {
-        hit = true;
+    // The shield and the terrain each cap the shot; the lower cap holds.
+    let terrain_cover = std::mem::take(&mut combatants[defender_idx].behind_cover);
+    let shield_cover = if is_ranged && shield_active {
+        combatants[defender_idx].shield_cover_value
+    } else {
+        None
+    };
+    let cover_cap = match (shield_cover, terrain_cover.filter(|_| is_ranged)) {
+        (Some(shield), Some(terrain)) => Some(shield.min(terrain)),
+        (shield, terrain) => shield.or(terrain),
+    };
+    if let Some(cap) = cover_cap {
+        attack_roll = attack_roll.min(cap);
+    }
+    let defense_roll =
+        defense_die + defense_die_mod + defense_mod + weapon_defense_bonus + shield_defense_bonus;
+    let defense_result =
+        DefenseResult::from_die(defense_sides, defense_die).filter(|_| rules.perfect_defense);
+    let roll = AttackRoll {
+        attacker: attacker_idx,
+        defender: defender_idx,
+        attacker_name: combatants[attacker_idx].name.clone(),
+        defender_name: combatants[defender_idx].name.clone(),
+        weapon_name,
+        is_ranged,
+        stance,
+        charge,
+        set_for_charge,
+        keeping_at_bay,
+        attack_die,
+        attack_bonus,
+        range_mod,
+        cover_cap,
+        attack_total: attack_roll,
+        position,
+        defense_die_sides: defense_sides,
+        defense_die,
+        defense_die_mod,
+        defense_mod,
+        weapon_defense_bonus,
+        shield_defense_bonus,
+        defense_total: defense_roll,
+        defense_result,
+        retreat,
+    };
+    let mut hit_damage = None;
+    let mut shield_hit = None;
+
+    if defense_result == Some(DefenseResult::Perfect) {
+        // Turned aside cleanly: no damage to the defender or their shield.
+    } else if keeping_at_bay {
+        if attack_roll >= defense_roll {
+            hit_damage = Some(DamageRoll {
+                detail: "[held at bay]".to_string(),
+                rolled: 0,
+                modifier: 0,
+                raw: 0,
+                dr_applied: 0,
+                damage: 0,
+            });
+        }
+    } else if attack_roll >= defense_roll {
         let jab_expr = jab_special_expr.as_deref().unwrap_or(&damage_expr);
         let (rolled_damage, detail) = if use_jab {
             roll_damage_expr_with_detail_nonpenetrating(jab_expr, rng)
+        } else if !stance.allows_penetration() {
+            roll_damage_expr_with_detail_nonpenetrating(&damage_expr, rng)
         } else {
             roll_damage_expr_with_detail(&damage_expr, rng)
         };
@@ -598,17 +2713,32 @@ fn resolve_attack(
         if raw < 0 {
             raw = 0;
         }
-        damage_detail = detail;
         let mut effective_dr = armor_dr;
         if armor_dr >= 5 || combatants[defender_idx].armor_is_heavy {
             effective_dr = (armor_dr - armor_penetration).max(0);
         }
-        damage = (raw - effective_dr).max(0);
+        let mut damage = (raw - effective_dr).max(0);
+        // A parry with a smaller off-hand weapon cannot blunt the blow.
+        let off_hand_parry = style.uses_two_weapons() && combatants[defender_idx].off_hand_small;
+        if defense_result == Some(DefenseResult::NearPerfect) && !off_hand_parry {
+            damage /= 2;
+        }
+        // An aggressive attack overcommits against a defender who backs away.
+        if retreat.is_some() && stance == Stance::AggressiveAttack {
+            damage /= 2;
+        }
         combatants[defender_idx].hp -= damage;
+        hit_damage = Some(DamageRoll {
+            detail,
+            rolled: rolled_damage,
+            modifier: strength_damage,
+            raw,
+            dr_applied: raw - damage,
+            damage,
+        });
     } else if shield_active && !is_ranged {
         let miss_margin = defense_roll - attack_roll;
         if miss_margin < 10 {
-            shield_block = true;
             let shield_expr = shield_damage_expr
                 .as_deref()
                 .filter(|expr| !expr.is_empty())
@@ -618,8 +2748,6 @@ fn resolve_attack(
             if raw < 0 {
                 raw = 0;
             }
-            shield_damage_detail = detail;
-            shield_damage = raw;
             let shield_dr = combatants[defender_idx].shield_dr;
             let shield_after_dr = (raw - shield_dr).max(0);
 
@@ -632,7 +2760,11 @@ fn resolve_attack(
                 combatants[defender_idx].hp -= hp_damage;
             }
 
-            if let Some(steps) = combatants[defender_idx].shield_breakage {
+            let mut shield_broken = false;
+            let breakage = combatants[defender_idx]
+                .shield_breakage
+                .filter(|_| rules.shield_breakage);
+            if let Some(steps) = breakage {
                 if raw >= steps[3].threshold {
                     shield_broken = true;
                 } else if raw >= steps[2].threshold {
@@ -646,75 +2778,57 @@ fn resolve_attack(
             if shield_broken {
                 combatants[defender_idx].shield_intact = false;
             }
+            let shield_damage = DamageRoll {
+                detail,
+                rolled: rolled_damage,
+                modifier: strength_damage,
+                raw,
+                dr_applied: raw - hp_damage,
+                damage: hp_damage,
+            };
+            shield_hit = Some((shield_damage, shield_broken));
         }
     }
 
-    let attacker_name = combatants[attacker_idx].name.clone();
-    let defender_name = combatants[defender_idx].name.clone();
     if !is_ranged {
-        if combatants[defender_idx].two_hand_grip
+        if style.defends_once_per_window()
             && combatants[defender_idx].defense_plus_four_ready
             && combatants[defender_idx].has_weapon
             && !combatants[defender_idx].weapon_defense_always
         {
             combatants[defender_idx].defense_plus_four_ready = false;
         }
-        if combatants[attacker_idx].two_hand_grip
+        if combatants[attacker_idx]
+            .current_style()
+            .defends_once_per_window()
             && combatants[attacker_idx].has_weapon
             && !combatants[attacker_idx].weapon_defense_always
         {
             combatants[attacker_idx].defense_plus_four_ready = true;
         }
     }
-    if hit {
-        format!(
-            "{} hits {} with {} (atk {} [d20p={}] vs def {} [d20p={}]) for {} dmg {} (hp {})",
-            attacker_name,
-            defender_name,
-            weapon_name,
-            attack_roll,
-            attack_die,
-            defense_roll,
-            defense_die,
+    combatants[attacker_idx].active_stance = stance;
+    let hp_after = combatants[defender_idx].hp;
+    if let Some(damage) = hit_damage {
+        CombatEvent::Hit {
+            roll,
             damage,
-            damage_detail,
-            combatants[defender_idx].hp.max(0)
-        )
-    } else if shield_block {
-        let shield_name = combatants[defender_idx]
-            .shield_name
-            .clone()
-            .unwrap_or_else(|| "Shield".to_string());
-        let status = if shield_broken {
-            "shield broken".to_string()
-        } else {
-            "shield intact".to_string()
-        };
-        format!(
-            "{} blocks {} with {} (atk {} [d20p={}] vs def {} [d20p={}]); shield dmg {} {} ({}), hp {}",
-            defender_name,
-            attacker_name,
-            shield_name,
-            attack_roll,
-            attack_die,
-            defense_roll,
-            defense_die,
+            hp_after,
+            critical: None,
+        }
+    } else if let Some((shield_damage, shield_broken)) = shield_hit {
+        CombatEvent::ShieldBlock {
+            roll,
+            shield_name: combatants[defender_idx]
+                .shield_name
+                .clone()
+                .unwrap_or_else(|| "Shield".to_string()),
             shield_damage,
-            shield_damage_detail,
-            status,
-            combatants[defender_idx].hp.max(0)
-        )
+            shield_broken,
+            hp_after,
+        }
     } else {
-        format!(
-            "{} misses {} with {} (atk {} [d20p={}] vs def {} [d20p={}])",
-            attacker_name,
-            defender_name,
-            weapon_name,
-            attack_roll,
-            attack_die,
-            defense_roll,
-            defense_die
-        )
+        CombatEvent::Miss { roll, fumble: None }
     }
 }
 
@@ -741,6 +2855,29 @@ fn roll_damage_expr_with_detail_nonpenetrating(expr: &str, rng: &mut impl Rng) -
     (total, format!("[{}]", detail))
 }
 
+/// Doubles the dice in a damage expression but not its flat bonus: `2d6p+3` becomes
+/// `4d6p+3`.
+fn double_dice_expr(expr: &str) -> String {
+    let mut doubled = String::new();
+    let mut count = String::new();
+    for ch in clean_damage_expr(expr).chars() {
+        if ch.is_ascii_digit() {
+            count.push(ch);
+            continue;
+        }
+        if ch == 'd' {
+            let dice = count.parse::<i32>().unwrap_or(1);
+            doubled.push_str(&(dice * 2).to_string());
+        } else {
+            doubled.push_str(&count);
+        }
+        count.clear();
+        doubled.push(ch);
+    }
+    doubled.push_str(&count);
+    doubled
+}
+
 fn clean_damage_expr(expr: &str) -> String {
     let first = expr.split(" and ").next().unwrap_or(expr);
     let lower = first.to_ascii_lowercase();
@@ -961,6 +3098,9 @@ fn has_top_level_operator(s: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::arena::{Area, TerrainFeature, TerrainKind};
+    use crate::tactic_rules::{ScriptedTactics, TacticRule};
+    use crate::tactics::TacticsKind;
     use rand::SeedableRng;
 
     fn combatant_basic(
@@ -1014,10 +3154,25 @@ mod tests {
 
     fn make_state(attacker: Combatant, defender: Combatant) -> SimState {
         let mut state = SimState::new(SimConfig::new(10.0, 1.0));
-        state.combatants = [attacker, defender];
+        state.combatants = vec![attacker, defender];
         state
     }
 
+    /// A one-point blade on a fighter who will not fall during the test.
+    fn sturdy_fighter(name: &str, weapon_speed: f32) -> Combatant {
+        Combatant {
+            name: name.to_string(),
+            weapon_name: "Test Blade".to_string(),
+            damage_expr: "1d1".to_string(),
+            has_weapon: true,
+            max_hp: 1000,
+            hp: 1000,
+            reach_ft: 5.0,
+            weapon_speed,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn attack_miss_does_no_damage() {
         let attacker = combatant_basic(
@@ -1062,7 +3217,15 @@ mod tests {
         );
         let mut state = make_state(attacker, defender);
         let mut rng = rand::rngs::StdRng::seed_from_u64(1);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
+        let _ = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
         assert_eq!(state.combatants[1].hp, 20);
     }
 
@@ -1110,7 +3273,12 @@ mod tests {
         );
         let mut state = make_state(attacker, defender);
         let mut rng = rand::rngs::StdRng::seed_from_u64(2);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
+        // This seed rolls a natural 19 on defense; keep the damage whole.
+        let rules = RuleToggles {
+            perfect_defense: false,
+            ..Default::default()
+        };
+        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, rules, &mut rng);
         assert_eq!(state.combatants[1].hp, 18);
     }
 
@@ -1158,9 +3326,17 @@ mod tests {
         );
         let mut state = make_state(attacker, defender);
         let mut rng = rand::rngs::StdRng::seed_from_u64(3);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
-        assert_eq!(state.combatants[1].hp, 18);
-    }
+        let _ = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
+        assert_eq!(state.combatants[1].hp, 18);
+    }
 
     #[test]
     fn negative_penetration_increases_effective_dr() {
@@ -1206,7 +3382,15 @@ mod tests {
         );
         let mut state = make_state(attacker, defender);
         let mut rng = rand::rngs::StdRng::seed_from_u64(4);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
+        let _ = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
         assert_eq!(state.combatants[1].hp, 20);
     }
 
@@ -1254,7 +3438,15 @@ mod tests {
         );
         let mut state = make_state(attacker, defender);
         let mut rng = rand::rngs::StdRng::seed_from_u64(5);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
+        let _ = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
         assert_eq!(state.combatants[1].hp, 20);
     }
 
@@ -1325,11 +3517,27 @@ mod tests {
         );
         let mut state = make_state(attacker, defender);
         let mut rng = FixedRng(0);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
+        let _ = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
         assert!(state.combatants[0].defense_plus_four_ready);
 
         let mut rng = FixedRng(0);
-        let _ = resolve_attack(&mut state.combatants, 1, 0, 0, false, &mut rng);
+        let _ = resolve_attack(
+            &mut state.combatants,
+            1,
+            0,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
         assert!(!state.combatants[0].defense_plus_four_ready);
     }
 
@@ -1377,7 +3585,15 @@ mod tests {
         );
         let mut state = make_state(attacker, defender);
         let mut rng = FixedRng(0);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
+        let _ = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
         assert_eq!(state.combatants[1].hp, 20);
     }
 
@@ -1454,6 +3670,1361 @@ mod tests {
         assert!(!state.combatants[1].moved_last_tick);
     }
 
+    #[test]
+    fn initiative_count_seeds_first_attack() {
+        let mut config = SimConfig::new(1.0, 1.0);
+        config.surprise_seconds = [0.0, 2.0];
+        config.hesitation_seconds = [1.0, 0.0];
+        let mut state = SimState::new(config);
+        let fighter = combatant_basic(
+            "Fighter".to_string(),
+            "Sword".to_string(),
+            0,
+            0,
+            0,
+            false,
+            0,
+            "1d1".to_string(),
+            0,
+            10.0,
+            1.0,
+            0.0,
+            false,
+            false,
+            None,
+            true,
+            false,
+            10,
+        )
+        .with_initiative(1, 3);
+        state.reset_with_combatants([fighter.clone(), fighter]);
+        assert_eq!(state.combatants[0].initiative_count, Some(5.0));
+        assert_eq!(state.combatants[1].initiative_count, Some(6.0));
+        assert_eq!(state.combatants[0].next_attack_time, Some(5.0));
+        assert_eq!(state.combatants[1].next_attack_time, Some(6.0));
+    }
+
+    #[test]
+    fn initiative_count_never_below_one() {
+        let mut state = SimState::new(SimConfig::new(1.0, 1.0));
+        let fighter = combatant_basic(
+            "Fighter".to_string(),
+            "Sword".to_string(),
+            0,
+            0,
+            0,
+            false,
+            0,
+            "1d1".to_string(),
+            0,
+            10.0,
+            1.0,
+            0.0,
+            false,
+            false,
+            None,
+            true,
+            false,
+            10,
+        )
+        .with_initiative(1, -5);
+        state.reset_with_combatants([fighter.clone(), fighter]);
+        assert_eq!(state.combatants[0].initiative_count, Some(1.0));
+        state.tick();
+        assert!(state.combatants.iter().all(|c| c.hp == c.max_hp));
+    }
+
+    #[test]
+    fn same_seed_replays_same_fight() {
+        let fighter = Combatant {
+            name: "Fighter".to_string(),
+            damage_expr: "2d6p".to_string(),
+            has_weapon: true,
+            max_hp: 20,
+            ..Default::default()
+        };
+        let run = |seed: u64| {
+            let mut state = SimState::new(SimConfig::new(10.0, 1.0).with_seed(seed));
+            state.reset_with_combatants([fighter.clone(), fighter.clone()]);
+            while !state.done && state.elapsed_seconds < 600 {
+                state.tick();
+            }
+            state.combat_log
+        };
+        let first = run(42);
+        assert_eq!(first[0].events[0], CombatEvent::Seed { seed: 42 });
+        assert_eq!(first, run(42));
+        assert_ne!(first[1..], run(43)[1..]);
+    }
+
+    #[test]
+    fn combat_log_tallies_match_final_hp() {
+        let fighter = Combatant {
+            name: "Fighter".to_string(),
+            damage_expr: "2d6p".to_string(),
+            has_weapon: true,
+            max_hp: 20,
+            ..Default::default()
+        };
+        let mut state = SimState::new(SimConfig::new(10.0, 1.0).with_seed(5));
+        state.reset_with_combatants([fighter.clone(), fighter.clone()]);
+        while !state.done && state.elapsed_seconds < 600 {
+            state.tick();
+        }
+        let stats = fight_stats(&state.combat_log, &state.sides());
+        for (side, tally) in stats.iter().enumerate() {
+            assert!(tally.hits <= tally.attacks);
+            let defender = &state.combatants[1 - side];
+            assert_eq!(defender.max_hp - defender.hp, tally.damage_dealt);
+        }
+        let last = state.last_entry().unwrap();
+        assert!(matches!(
+            last.events.last(),
+            Some(CombatEvent::Defeat { .. })
+        ));
+        assert!(
+            last.to_string()
+                .starts_with(&format!("t={}s | ", last.time))
+        );
+
+        // Blocked blows that get through the shield count too.
+        let shieldbearer = Combatant {
+            shield_name: Some("Buckler".to_string()),
+            shield_intact: true,
+            ..fighter.clone()
+        };
+        let mut state = SimState::new(SimConfig::new(10.0, 1.0).with_seed(5));
+        state.config.rules.shield_breakage = false;
+        state.reset_with_combatants([fighter, shieldbearer]);
+        while !state.done && state.elapsed_seconds < 600 {
+            state.tick();
+        }
+        let through_shield: i32 = state
+            .combat_log
+            .iter()
+            .flat_map(|entry| &entry.events)
+            .filter_map(|event| match event {
+                CombatEvent::ShieldBlock { shield_damage, .. } => Some(shield_damage.damage),
+                _ => None,
+            })
+            .sum();
+        assert!(through_shield > 0);
+        let stats = fight_stats(&state.combat_log, &state.sides());
+        let shieldbearer = &state.combatants[1];
+        assert_eq!(shieldbearer.max_hp - shieldbearer.hp, stats[0].damage_dealt);
+    }
+
+    #[test]
+    fn shield_breakage_toggle_keeps_shield_intact() {
+        let attacker = Combatant {
+            name: "Attacker".to_string(),
+            damage_expr: "1d1".to_string(),
+            has_weapon: true,
+            max_hp: 20,
+            ..Default::default()
+        };
+        let defender = Combatant {
+            name: "Defender".to_string(),
+            max_hp: 1000,
+            hp: 1000,
+            shield_name: Some("Buckler".to_string()),
+            shield_intact: true,
+            shield_breakage: Some(
+                [ShieldBreakageStep {
+                    threshold: 0,
+                    save_mod: None,
+                }; 4],
+            ),
+            ..Default::default()
+        };
+        let rules = RuleToggles {
+            shield_breakage: false,
+            ..Default::default()
+        };
+        let mut state = make_state(attacker, defender);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(4);
+        let mut blocks = 0;
+        for _ in 0..200 {
+            let event = resolve_attack(&mut state.combatants, 0, 1, 0, false, rules, &mut rng);
+            if matches!(event, CombatEvent::ShieldBlock { .. }) {
+                blocks += 1;
+            }
+        }
+        assert!(blocks > 0);
+        assert!(state.combatants[1].shield_intact);
+
+        let event = loop {
+            let event = resolve_attack(
+                &mut state.combatants,
+                0,
+                1,
+                0,
+                false,
+                RuleToggles::default(),
+                &mut rng,
+            );
+            if matches!(event, CombatEvent::ShieldBlock { .. }) {
+                break event;
+            }
+        };
+        assert!(matches!(
+            event,
+            CombatEvent::ShieldBlock {
+                shield_broken: true,
+                ..
+            }
+        ));
+        assert!(!state.combatants[1].shield_intact);
+    }
+
+    #[test]
+    fn failed_trauma_save_incapacitates() {
+        let mut defender = Combatant {
+            name: "Defender".to_string(),
+            max_hp: 30,
+            hp: 20,
+            next_attack_time: Some(3.0),
+            ..Default::default()
+        }
+        .with_threshold_of_pain(5, 0);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        assert!(check_threshold_of_pain(&mut defender, 1, 5, 2.0, &mut rng).is_none());
+
+        let event = check_threshold_of_pain(&mut defender, 1, 6, 2.0, &mut rng).unwrap();
+        let CombatEvent::ThresholdOfPain {
+            incapacitated_seconds: Some(seconds),
+            ..
+        } = event
+        else {
+            panic!("expected a failed trauma save, got {event:?}");
+        };
+        assert!(seconds >= 5);
+        assert_eq!(
+            defender.statuses.until(StatusKind::Incapacitated),
+            Some(2.0 + seconds as f32)
+        );
+        assert!(!defender.statuses.modifiers().can_act);
+    }
+
+    #[test]
+    fn incapacitated_fighter_skips_attacks_until_status_ends() {
+        // One-handed, so a second faster: an attack every 2 seconds.
+        let mut state = make_state(sturdy_fighter("A", 3.0), sturdy_fighter("B", 3.0));
+        state.config.rules.knockback = false;
+        state.config.stop_distance = 5.0;
+        state.actors[1].position.x = state.actors[0].position.x + 5.0;
+        state.combatants[0]
+            .statuses
+            .apply(StatusKind::Incapacitated, 4.0);
+        let mut attacks_by_a = Vec::new();
+        let mut recovered_at = None;
+        for _ in 0..8 {
+            state.tick();
+        }
+        for entry in &state.combat_log {
+            for event in &entry.events {
+                match event {
+                    CombatEvent::StatusEnded {
+                        combatant: 0,
+                        status: StatusKind::Incapacitated,
+                        ..
+                    } => recovered_at = Some(entry.time),
+                    event if event.attack_roll().is_some_and(|roll| roll.attacker == 0) => {
+                        attacks_by_a.push(entry.time)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        assert_eq!(recovered_at, Some(4));
+        assert_eq!(attacks_by_a, vec![4, 6]);
+        assert!(state.combatants[0].statuses.is_empty());
+    }
+
+    fn off_hand_dagger() -> WeaponLoadout {
+        WeaponLoadout {
+            weapon_name: "Dagger".to_string(),
+            damage_expr: "1d1".to_string(),
+            shield_damage_expr: None,
+            strength_damage: 0,
+            armor_penetration: 0,
+            weapon_speed: 3.0,
+            reach_ft: 1.0,
+            two_hand_grip: false,
+            use_jab: false,
+            jab_speed: None,
+            jab_special_expr: None,
+            has_weapon: true,
+            weapon_defense_always: false,
+            set_for_charge: false,
+            draw_seconds: 0.0,
+        }
+    }
+
+    #[test]
+    fn offensive_two_weapons_strike_with_the_off_hand_and_defend_on_a_d10() {
+        let fighter = |name: &str, weapon_speed: f32| Combatant {
+            strength_damage: 3,
+            ..sturdy_fighter(name, weapon_speed)
+        };
+        let mut state = make_state(
+            fighter("A", 4.0)
+                .with_fighting_style(FightingStyle::TwoWeaponsOffensive)
+                .with_off_hand(Some(off_hand_dagger()), true),
+            fighter("B", 10.0),
+        );
+        state.config.rules.knockback = false;
+        state.config.stop_distance = 5.0;
+        state.actors[1].position.x = state.actors[0].position.x + 5.0;
+        for _ in 0..16 {
+            state.tick();
+        }
+        let times = |weapon: &str| -> Vec<u32> {
+            attacks_by(&state, 0)
+                .into_iter()
+                .filter(|(_, roll)| roll.weapon_name == weapon)
+                .map(|(time, _)| time)
+                .collect()
+        };
+        // Primary every 4 + 2 seconds; the dagger 2 + 4 / 2 after the first primary
+        // attack, then every 3 + 2.
+        let primary = times("Test Blade");
+        let start = primary[0];
+        assert_eq!(primary[..3], [start, start + 6, start + 12]);
+        assert_eq!(times("Dagger")[..2], [start + 4, start + 9]);
+        assert_eq!(state.combatants[0].weapon_name, "Test Blade");
+        assert_eq!(state.combatants[0].strength_damage, 3);
+        assert!(
+            attacks_by(&state, 1)
+                .iter()
+                .all(|(_, roll)| roll.defense_die_sides == 10 && roll.weapon_defense_bonus == 4)
+        );
+    }
+
+    #[test]
+    fn defensive_off_hand_adds_its_bonus_once_per_window() {
+        let fighter = |name: &str| Combatant {
+            defense_plus_four_ready: true,
+            ..sturdy_fighter(name, 9.0)
+        };
+        let defense = |defender: Combatant| {
+            let mut state = make_state(fighter("A"), defender);
+            (0..2)
+                .map(|_| {
+                    let event = resolve_attack(
+                        &mut state.combatants,
+                        0,
+                        1,
+                        0,
+                        false,
+                        RuleToggles::default(),
+                        &mut FixedRng(0),
+                    );
+                    event.attack_roll().unwrap().weapon_defense_bonus
+                })
+                .collect::<Vec<_>>()
+        };
+        let two_weapons = fighter("B")
+            .with_fighting_style(FightingStyle::TwoWeaponsDefensive)
+            .with_off_hand(Some(off_hand_dagger()), true);
+        assert_eq!(defense(two_weapons), vec![8, 0]);
+        assert_eq!(defense(fighter("B")), vec![0, 0]);
+    }
+
+    #[test]
+    fn two_shields_guard_the_flank_and_halve_opponent_speed() {
+        let defender = Combatant {
+            shield_name: Some("Buckler".to_string()),
+            shield_intact: true,
+            shield_defense_bonus: 1,
+            ..sturdy_fighter("B", 9.0)
+        }
+        .with_fighting_style(FightingStyle::TwoShields)
+        .with_second_shield(2);
+        let mut state = make_state(sturdy_fighter("A", 9.0), defender);
+        state.combatants[1].attacked_from = AttackPosition {
+            angle: AttackAngle::Flank,
+            ..AttackPosition::default()
+        };
+        let event = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut FixedRng(0),
+        );
+        let roll = event.attack_roll().unwrap();
+        assert_eq!(roll.shield_defense_bonus, 4 + 1 + 2);
+
+        state.combatants[1].attacked_from = AttackPosition::default();
+        state.config.rules.knockback = false;
+        state.config.stop_distance = 5.0;
+        state.actors[1].position.x = state.actors[0].position.x + 5.0;
+        for _ in 0..12 {
+            state.tick();
+        }
+        // One-handed at 9 - 1 seconds, halved against a fighter with no weapon to fear.
+        let times: Vec<u32> = attacks_by(&state, 0)
+            .iter()
+            .map(|(time, _)| *time)
+            .collect();
+        assert_eq!(times[1] - times[0], 4);
+    }
+
+    #[test]
+    fn knockdown_delays_next_attack_until_standing() {
+        let mut defender = Combatant {
+            name: "Defender".to_string(),
+            max_hp: 100,
+            hp: 60,
+            next_attack_time: Some(4.0),
+            ..Default::default()
+        };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        assert!(check_knockback(&mut defender, 1, 9, 3.0, &mut rng).is_none());
+
+        let event = check_knockback(&mut defender, 1, 60, 3.0, &mut rng).unwrap();
+        let CombatEvent::Knockback {
+            feet,
+            knocked_down: true,
+            ..
+        } = event
+        else {
+            panic!("expected a knockdown, got {event:?}");
+        };
+        assert_eq!(feet, 30.0);
+        assert_eq!(defender.statuses.until(StatusKind::Prone), Some(5.0));
+        assert_eq!(defender.next_attack_time, Some(5.0));
+    }
+
+    #[test]
+    fn knockback_pushes_defender_away_and_stands_them_up() {
+        let attacker = Combatant {
+            name: "Ogre".to_string(),
+            weapon_name: "Test Club".to_string(),
+            damage_expr: "1d1".to_string(),
+            strength_damage: 19,
+            attack_bonus: 100,
+            has_weapon: true,
+            max_hp: 50,
+            hp: 50,
+            reach_ft: 5.0,
+            weapon_speed: 30.0,
+            move_speed: 0.0,
+            ..Default::default()
+        };
+        let defender = Combatant {
+            name: "Target".to_string(),
+            max_hp: 1000,
+            hp: 1000,
+            reach_ft: 5.0,
+            ..Default::default()
+        };
+        let mut state = make_state(attacker, defender);
+        state.config.rules.threshold_of_pain = false;
+        state.config.stop_distance = 5.0;
+        state.actors[1].position.x = state.actors[0].position.x + 5.0;
+        let feet = loop {
+            state.tick();
+            let knockback = state.last_entry().and_then(|entry| {
+                entry.events.iter().find_map(|event| match event {
+                    CombatEvent::Knockback { feet, .. } => Some(*feet),
+                    _ => None,
+                })
+            });
+            if let Some(feet) = knockback {
+                break feet;
+            }
+            assert!(state.elapsed_seconds < 60, "no knockback landed");
+        };
+        assert_eq!(feet, 10.0);
+        assert_eq!(state.distance(), 5.0 + feet);
+        for _ in 0..3 {
+            state.tick();
+        }
+        assert!(!state.combatants[1].statuses.has(StatusKind::Prone));
+    }
+
+    #[test]
+    fn threshold_of_pain_toggle_skips_trauma_saves() {
+        let fighter = |name: &str| {
+            Combatant {
+                name: name.to_string(),
+                weapon_name: "Test Blade".to_string(),
+                damage_expr: "2d6p".to_string(),
+                has_weapon: true,
+                max_hp: 40,
+                hp: 40,
+                ..Default::default()
+            }
+            .with_threshold_of_pain(0, 0)
+        };
+        let has_trauma = |rules: RuleToggles| {
+            let mut config = SimConfig::new(10.0, 1.0).with_seed(5);
+            config.rules = rules;
+            let mut sim = SimState::new(config);
+            sim.reset_with_combatants([fighter("A"), fighter("B")]);
+            crate::batch::run_duel(&mut sim, 600);
+            sim.combat_log
+                .iter()
+                .flat_map(|entry| &entry.events)
+                .any(|event| matches!(event, CombatEvent::ThresholdOfPain { .. }))
+        };
+        assert!(has_trauma(RuleToggles::default()));
+        assert!(!has_trauma(RuleToggles {
+            threshold_of_pain: false,
+            ..Default::default()
+        }));
+    }
+
+    fn duelists_for_tables(attack_bonus: i32) -> SimState {
+        let attacker = Combatant {
+            name: "Attacker".to_string(),
+            damage_expr: "1d1".to_string(),
+            strength_damage: 4,
+            attack_bonus,
+            has_weapon: true,
+            max_hp: 1000,
+            hp: 1000,
+            next_attack_time: Some(10.0),
+            ..Default::default()
+        };
+        let defender = Combatant {
+            name: "Defender".to_string(),
+            armor_dr: 2,
+            max_hp: 1000,
+            hp: 1000,
+            ..Default::default()
+        };
+        make_state(attacker, defender)
+    }
+
+    #[test]
+    fn natural_twenty_hits_roll_on_the_critical_table() {
+        let mut state = duelists_for_tables(100);
+        let table = CriticalTable::default();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        let rules = RuleToggles {
+            perfect_defense: false,
+            ..Default::default()
+        };
+        let event = loop {
+            let hp_before = state.combatants[1].hp;
+            let mut event = resolve_attack(&mut state.combatants, 0, 1, 0, false, rules, &mut rng);
+            apply_critical(&mut event, &mut state.combatants[1], &table, 3.0, &mut rng);
+            let CombatEvent::Hit {
+                roll,
+                damage,
+                hp_after,
+                critical,
+            } = &event
+            else {
+                panic!("expected a hit, got {event:?}");
+            };
+            assert_eq!(*hp_after, hp_before - damage.damage);
+            if roll.attack_die < 20 {
+                assert!(critical.is_none());
+                continue;
+            }
+            break event;
+        };
+        let CombatEvent::Hit {
+            roll,
+            damage,
+            critical: Some(critical),
+            ..
+        } = event
+        else {
+            panic!("natural 20 did not crit");
+        };
+        assert_eq!(critical.severity, roll.attack_total - roll.defense_total);
+        assert_eq!(damage.damage, 3 + critical.extra_damage);
+        if let Some(status) = critical.status {
+            assert_eq!(
+                state.combatants[1].statuses.until(status),
+                Some(3.0 + critical.status_seconds)
+            );
+        }
+    }
+
+    #[test]
+    fn natural_one_misses_roll_on_the_fumble_table() {
+        let mut state = duelists_for_tables(-100);
+        let table = CriticalTable::default();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        let fumble = loop {
+            let mut event = resolve_attack(
+                &mut state.combatants,
+                0,
+                1,
+                0,
+                false,
+                RuleToggles::default(),
+                &mut rng,
+            );
+            apply_fumble(&mut event, &mut state.combatants[0], &table, 3.0, &mut rng);
+            if let CombatEvent::Miss {
+                fumble: Some(fumble),
+                ..
+            } = event
+            {
+                break fumble;
+            }
+        };
+        assert_eq!(
+            table.fumble_for(fumble.roll).unwrap().name,
+            fumble.name.as_str()
+        );
+        assert_eq!(
+            state.combatants[0].next_attack_time,
+            Some(10.0 + fumble.delay_seconds)
+        );
+    }
+
+    #[test]
+    fn perfect_defense_turns_even_sure_hits() {
+        let count_perfect = |rules: RuleToggles| {
+            let mut state = duelists_for_tables(100);
+            let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+            (0..200)
+                .filter(|_| {
+                    let event =
+                        resolve_attack(&mut state.combatants, 0, 1, 0, false, rules, &mut rng);
+                    let perfect =
+                        event.attack_roll().unwrap().defense_result == Some(DefenseResult::Perfect);
+                    assert_eq!(perfect, matches!(event, CombatEvent::Miss { .. }));
+                    perfect
+                })
+                .count()
+        };
+        assert!(count_perfect(RuleToggles::default()) > 0);
+        assert_eq!(
+            count_perfect(RuleToggles {
+                perfect_defense: false,
+                ..Default::default()
+            }),
+            0
+        );
+    }
+
+    #[test]
+    fn stances_shift_attack_now_and_defense_until_the_next_attack() {
+        let mut state = duelists_for_tables(0);
+        state.combatants[0].stance = Stance::AggressiveAttack;
+        state.combatants[1].stance = Stance::FightDefensively(2);
+        let rules = RuleToggles::default();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+
+        let event = resolve_attack(&mut state.combatants, 1, 0, 0, false, rules, &mut rng);
+        let roll = event.attack_roll().unwrap();
+        assert_eq!(roll.attack_bonus, -4);
+        assert_eq!(roll.defense_mod, 0);
+        assert_eq!(
+            state.combatants[1].active_stance,
+            Stance::FightDefensively(2)
+        );
+
+        let event = resolve_attack(&mut state.combatants, 0, 1, 0, false, rules, &mut rng);
+        let roll = event.attack_roll().unwrap();
+        assert_eq!(roll.attack_bonus, 5);
+        assert_eq!(roll.defense_mod, 2);
+
+        let event = resolve_attack(&mut state.combatants, 1, 0, 0, false, rules, &mut rng);
+        assert_eq!(event.attack_roll().unwrap().defense_mod, -2);
+    }
+
+    #[test]
+    fn fighting_defensively_hits_do_not_penetrate() {
+        let mut state = duelists_for_tables(100);
+        state.combatants[0].damage_expr = "1d2".to_string();
+        state.combatants[0].stance = Stance::FightDefensively(4);
+        let rules = RuleToggles {
+            perfect_defense: false,
+            ..Default::default()
+        };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(6);
+        for _ in 0..200 {
+            let event = resolve_attack(&mut state.combatants, 0, 1, 0, false, rules, &mut rng);
+            let CombatEvent::Hit { damage, .. } = event else {
+                panic!("expected a hit, got {event:?}");
+            };
+            assert!(damage.rolled <= 2, "penetrated: {}", damage.detail);
+        }
+    }
+
+    #[test]
+    fn giving_ground_trades_defense_now_for_the_next_attack() {
+        let mut state = duelists_for_tables(100);
+        state.combatants[1].retreat = Some(Retreat::ScamperBack);
+        let rules = RuleToggles {
+            perfect_defense: false,
+            criticals: false,
+            ..Default::default()
+        };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let event = resolve_attack(&mut state.combatants, 0, 1, 0, false, rules, &mut rng);
+        let roll = event.attack_roll().unwrap();
+        assert_eq!(roll.retreat, Some(Retreat::ScamperBack));
+        assert_eq!(roll.defense_mod, 5);
+        assert_eq!(state.combatants[1].pending_attack_mod, -4);
+
+        let event = resolve_attack(&mut state.combatants, 1, 0, 0, false, rules, &mut rng);
+        assert_eq!(event.attack_roll().unwrap().attack_bonus, -4);
+        assert_eq!(state.combatants[1].pending_attack_mod, 0);
+
+        state.combatants[0].stance = Stance::AggressiveAttack;
+        state.combatants[0].damage_expr = "1d1+9".to_string();
+        let event = resolve_attack(&mut state.combatants, 0, 1, 0, false, rules, &mut rng);
+        let CombatEvent::Hit { damage, .. } = event else {
+            panic!("expected a hit, got {event:?}");
+        };
+        assert_eq!(damage.damage, (14 - 2) / 2);
+
+        state.combatants[0].move_speed = state.combatants[1].move_speed + 5.0;
+        let event = resolve_attack(&mut state.combatants, 0, 1, 0, false, rules, &mut rng);
+        assert_eq!(event.attack_roll().unwrap().retreat, None);
+    }
+
+    #[test]
+    fn retreating_defender_is_followed_down_the_line() {
+        let mut state = make_state(sturdy_fighter("A", 3.0), sturdy_fighter("B", 3.0));
+        state.combatants[1].retreat = Some(Retreat::GiveGround);
+        state.combatants[1].turn_action = TurnAction::FullParry;
+        state.config.stop_distance = 5.0;
+        state.actors[1].position.x = state.actors[0].position.x + 5.0;
+        for _ in 0..7 {
+            state.tick();
+        }
+        let events: Vec<&CombatEvent> = state
+            .combat_log
+            .iter()
+            .flat_map(|entry| &entry.events)
+            .collect();
+        let attacks_by_a = events
+            .iter()
+            .filter(|event| event.attack_roll().is_some_and(|roll| roll.attacker == 0))
+            .count();
+        assert!(attacks_by_a >= 2);
+        assert!(
+            events
+                .iter()
+                .all(|event| event.attack_roll().is_none_or(|roll| roll.attacker == 0))
+        );
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, CombatEvent::FullParry { combatant: 1, .. }))
+        );
+        assert_eq!(state.actors[0].position.x, 5.0 * attacks_by_a as f32);
+        assert_eq!(state.distance(), 5.0);
+    }
+
+    fn charge_duel(charger_speed: f32) -> SimState {
+        // An attack every 3 seconds, one-handed.
+        let mut state = make_state(
+            Combatant {
+                move_speed: charger_speed,
+                ..sturdy_fighter("Charger", 4.0)
+            }
+            .with_charge(true, false, 3),
+            sturdy_fighter("Pikeman", 4.0),
+        );
+        state.combatants[0].defense_mod = 3;
+        state.config.stop_distance = 5.0;
+        state.config.rules.perfect_defense = false;
+        state.actors[1].position.x = 40.0;
+        state
+    }
+
+    #[test]
+    fn charge_needs_a_run_and_trades_dex_defense_for_attack() {
+        let mut state = charge_duel(20.0);
+        state.combatants[1].move_speed = 0.0;
+        for _ in 0..12 {
+            state.tick();
+        }
+        let events: Vec<&CombatEvent> = state
+            .combat_log
+            .iter()
+            .flat_map(|entry| &entry.events)
+            .collect();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            CombatEvent::Charge { combatant: 0, feet, .. } if *feet == 40.0
+        )));
+        let charger_rolls: Vec<&AttackRoll> = events
+            .iter()
+            .filter_map(|event| event.attack_roll())
+            .filter(|roll| roll.attacker == 0)
+            .collect();
+        assert!(charger_rolls[0].charge && charger_rolls[0].attack_bonus == 4);
+        assert!(charger_rolls[1..].iter().all(|roll| !roll.charge));
+        // Contact at t=2; the Dex bonus is gone for the pikeman's attacks at t=2 and t=5.
+        let defense_mods: Vec<i32> = events
+            .iter()
+            .filter_map(|event| event.attack_roll())
+            .filter(|roll| roll.attacker == 1)
+            .map(|roll| roll.defense_mod)
+            .collect();
+        assert_eq!(defense_mods, vec![0, 0, 3, 3]);
+    }
+
+    #[test]
+    fn set_weapon_strikes_a_charger_first_with_double_dice() {
+        let mut state = charge_duel(10.0);
+        state.combatants[1].set_for_charge = true;
+        state.combatants[1].attack_bonus = 100;
+        state.combatants[1].damage_expr = "2d1+1".to_string();
+        state.combatants[1].move_speed = 20.0;
+        for _ in 0..6 {
+            state.tick();
+        }
+        assert_eq!(state.actors[1].position.x, 40.0);
+        let events: Vec<&CombatEvent> = state
+            .combat_log
+            .iter()
+            .flat_map(|entry| &entry.events)
+            .collect();
+        let first_attack = events
+            .iter()
+            .position(|event| event.attack_roll().is_some())
+            .unwrap();
+        let CombatEvent::Hit { roll, damage, .. } = events[first_attack] else {
+            panic!("set strike should hit: {}", events[first_attack]);
+        };
+        assert!(roll.attacker == 1 && roll.set_for_charge);
+        assert_eq!(damage.rolled, 5);
+        assert!(matches!(
+            events[first_attack + 1],
+            CombatEvent::ChargeBroken { combatant: 0, .. }
+        ));
+        // Held at bay, the charger only attacks once they knock the set weapon aside.
+        let slipped_inside = events.iter().position(|event| {
+            matches!(
+                event,
+                CombatEvent::KnockAside {
+                    combatant: 0,
+                    success: true,
+                    ..
+                }
+            )
+        });
+        for (at, event) in events.iter().enumerate() {
+            if event.attack_roll().is_some_and(|roll| roll.attacker == 0) {
+                assert!(slipped_inside.is_some_and(|slipped| slipped < at));
+            }
+        }
+    }
+
+    #[test]
+    fn charge_damage_counts_double_for_knockback() {
+        let mut state = charge_duel(20.0);
+        state.combatants[0].attack_bonus = 100;
+        state.combatants[0].damage_expr = "6d1".to_string();
+        state.combatants[0].charging = true;
+        state.actors[1].position.x = 5.0;
+        let events = state.strike(0, 1, 0, false);
+        assert!(matches!(&events[0], CombatEvent::Hit { roll, .. } if roll.charge));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            CombatEvent::Knockback { damage, .. } if *damage >= 12
+        )));
+    }
+
+    fn reach_duel(entering_attack_bonus: i32) -> SimState {
+        let fighter = |name: &str, reach_ft: f32| Combatant {
+            name: name.to_string(),
+            weapon_name: "Test Weapon".to_string(),
+            damage_expr: "1d1".to_string(),
+            has_weapon: true,
+            max_hp: 1000,
+            hp: 1000,
+            reach_ft,
+            weapon_speed: 3.0,
+            ..Default::default()
+        };
+        let mut state = make_state(
+            Combatant {
+                attack_bonus: entering_attack_bonus,
+                move_speed: 20.0,
+                ..fighter("Swordsman", 3.0)
+            },
+            Combatant {
+                attack_bonus: 100,
+                move_speed: 0.0,
+                ..fighter("Pikeman", 10.0)
+            },
+        );
+        state.config.stop_distance = 10.0;
+        state.config.rules.perfect_defense = false;
+        state.config.rules.fumbles = false;
+        state.actors[1].position.x = 30.0;
+        state
+    }
+
+    #[test]
+    fn longer_reach_holds_an_entering_fighter_at_bay() {
+        let mut state = reach_duel(-100);
+        for _ in 0..10 {
+            state.tick();
+        }
+        assert_eq!(state.distance(), 10.0);
+        assert_eq!(state.combatants[0].hp, 1000);
+        assert!(state.combatants[0].statuses.has(StatusKind::HeldAtBay));
+        let events: Vec<&CombatEvent> = state
+            .combat_log
+            .iter()
+            .flat_map(|entry| &entry.events)
+            .collect();
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, CombatEvent::HeldAtBay { combatant: 0, .. }))
+        );
+        assert!(events.iter().any(|event| matches!(
+            event,
+            CombatEvent::KnockAside {
+                combatant: 0,
+                success: false,
+                ..
+            }
+        )));
+        let rolls: Vec<&AttackRoll> = events.iter().filter_map(|e| e.attack_roll()).collect();
+        assert!(!rolls.is_empty());
+        assert!(
+            rolls
+                .iter()
+                .all(|roll| roll.attacker == 1 && roll.keeping_at_bay)
+        );
+    }
+
+    #[test]
+    fn held_fighter_closes_once_the_holder_retreats_out_of_reach() {
+        let mut state = reach_duel(-100);
+        for _ in 0..10 {
+            state.tick();
+        }
+        assert_eq!(state.combatants[0].held_by, Some(1));
+        let retreat = TacticRule::parse("if distance <= 20 then retreat").unwrap();
+        let holder = &mut state.combatants[1];
+        holder.move_speed = 20.0;
+        holder.tactics = Arc::new(ScriptedTactics::new(
+            vec![retreat],
+            Arc::new(StandardTactics),
+        ));
+        let held_since = state.elapsed_seconds;
+        // Freed once the pike backs out of reach, the swordsman follows it in.
+        for _ in 0..2 {
+            state.tick();
+        }
+        let events: Vec<&CombatEvent> = state
+            .combat_log
+            .iter()
+            .filter(|entry| entry.time >= held_since)
+            .flat_map(|entry| &entry.events)
+            .collect();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            CombatEvent::StatusEnded {
+                combatant: 0,
+                status: StatusKind::HeldAtBay,
+                ..
+            }
+        )));
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, CombatEvent::Move { combatant: 0, .. }))
+        );
+    }
+
+    #[test]
+    fn knocking_the_reach_aside_lets_the_held_fighter_in_next_second() {
+        let mut state = reach_duel(100);
+        for _ in 0..4 {
+            state.tick();
+        }
+        let events: Vec<(u32, &CombatEvent)> = state
+            .combat_log
+            .iter()
+            .flat_map(|entry| entry.events.iter().map(move |event| (entry.time, event)))
+            .collect();
+        let slipped_at = events
+            .iter()
+            .find(|(_, event)| {
+                matches!(
+                    event,
+                    CombatEvent::KnockAside {
+                        combatant: 0,
+                        success: true,
+                        ..
+                    }
+                )
+            })
+            .map(|(time, _)| *time)
+            .unwrap();
+        let first_attack_at = events
+            .iter()
+            .find(|(_, event)| event.attack_roll().is_some_and(|roll| roll.attacker == 0))
+            .map(|(time, _)| *time);
+        assert_eq!(first_attack_at, Some(slipped_at + 1));
+        assert_eq!(state.distance(), 3.0);
+    }
+
+    #[test]
+    fn jab_capable_holder_only_jabs_a_held_fighter() {
+        let mut state = reach_duel(0);
+        state.combatants[1].strength_damage = 9;
+        state.combatants[0]
+            .statuses
+            .apply(StatusKind::HeldAtBay, f32::INFINITY);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let rules = state.config.rules;
+        let poke = resolve_attack(&mut state.combatants, 1, 0, 0, false, rules, &mut rng);
+        assert!(matches!(&poke, CombatEvent::Hit { roll, damage, .. }
+            if roll.keeping_at_bay && damage.damage == 0));
+        state.combatants[1].jab_speed = Some(2.0);
+        let jab = resolve_attack(&mut state.combatants, 1, 0, 0, false, rules, &mut rng);
+        // Half of 1 + 9, not the full blow.
+        assert!(matches!(&jab, CombatEvent::Hit { roll, damage, .. }
+            if !roll.keeping_at_bay && damage.raw == 5 && damage.damage == 5));
+        assert_eq!(state.combatants[1].attack_speed(true), 2.0);
+        assert_eq!(state.combatants[1].attack_speed(false), 3.0);
+    }
+
+    /// Draws the reserve weapon on the first attack, then fights with it.
+    #[derive(Debug)]
+    struct SwapOnce;
+
+    impl Tactics for SwapOnce {
+        fn name(&self) -> &str {
+            "Swap once"
+        }
+
+        fn decide(&self, situation: &Situation) -> Intent {
+            Intent {
+                action: if situation.me.weapon_swapped {
+                    TurnAction::Jab
+                } else {
+                    TurnAction::SwapWeapon
+                },
+                ..StandardTactics.decide(situation)
+            }
+        }
+    }
+
+    fn tactics_duel(tactics: Arc<dyn Tactics>) -> SimState {
+        let fighter = |name: &str| Combatant {
+            reach_ft: 3.0,
+            ..sturdy_fighter(name, 10.0)
+        };
+        let mut state = make_state(fighter("Chooser").with_tactics(tactics), fighter("Dummy"));
+        state.config.rules.perfect_defense = false;
+        state.config.rules.fumbles = false;
+        state
+    }
+
+    fn attacks_by(state: &SimState, attacker: usize) -> Vec<(u32, &AttackRoll)> {
+        state
+            .combat_log
+            .iter()
+            .flat_map(|entry| entry.events.iter().map(move |event| (entry.time, event)))
+            .filter_map(|(time, event)| event.attack_roll().map(|roll| (time, roll)))
+            .filter(|(_, roll)| roll.attacker == attacker)
+            .collect()
+    }
+
+    #[test]
+    fn tactics_choose_the_stance_for_each_attack() {
+        let mut state = tactics_duel(TacticsKind::Berserk.tactics());
+        for _ in 0..30 {
+            state.tick();
+        }
+        let attacks = attacks_by(&state, 0);
+        assert!(!attacks.is_empty());
+        assert!(
+            attacks
+                .iter()
+                .all(|(_, roll)| roll.stance == Stance::AggressiveAttack)
+        );
+        assert!(
+            attacks_by(&state, 1)
+                .iter()
+                .all(|(_, roll)| roll.stance == Stance::Normal)
+        );
+    }
+
+    #[test]
+    fn swapping_weapons_waits_out_the_draw_then_jabs_at_jab_speed() {
+        let mut state = tactics_duel(Arc::new(SwapOnce));
+        state.combatants[0].reserve_weapon = Some(WeaponLoadout {
+            weapon_name: "Spear".to_string(),
+            damage_expr: "1d1".to_string(),
+            shield_damage_expr: None,
+            strength_damage: 0,
+            armor_penetration: 0,
+            weapon_speed: 8.0,
+            reach_ft: 3.0,
+            two_hand_grip: false,
+            use_jab: false,
+            jab_speed: Some(5.0),
+            jab_special_expr: None,
+            has_weapon: true,
+            weapon_defense_always: false,
+            set_for_charge: false,
+            draw_seconds: 1.0,
+        });
+        for _ in 0..40 {
+            state.tick();
+        }
+        let ready_at = state
+            .combat_log
+            .iter()
+            .flat_map(|entry| entry.events.iter())
+            .find_map(|event| match event {
+                CombatEvent::WeaponSwap {
+                    combatant: 0,
+                    weapon_name,
+                    ready_at,
+                    ..
+                } => {
+                    assert_eq!(weapon_name, "Spear");
+                    Some(*ready_at)
+                }
+                _ => None,
+            })
+            .unwrap();
+        let attacks = attacks_by(&state, 0);
+        assert!(attacks.len() >= 2);
+        assert!(attacks.iter().all(|(_, roll)| roll.weapon_name == "Spear"));
+        assert_eq!(attacks[0].0 as f32, ready_at);
+        assert_eq!(attacks[1].0 - attacks[0].0, 4);
+
+        state.combatants[0].reset_hp();
+        assert_eq!(state.combatants[0].weapon_name, "Test Blade");
+        assert_eq!(state.combatants[0].weapon_speed, 10.0);
+    }
+
+    #[test]
+    fn scripted_rule_takes_a_two_hand_grip_once_the_shield_breaks() {
+        let rule = TacticRule::parse("if shield broken then switch to two-hand grip").unwrap();
+        let mut state = tactics_duel(Arc::new(ScriptedTactics::new(
+            vec![rule],
+            Arc::new(StandardTactics),
+        )));
+        let fighter = &mut state.combatants[0];
+        fighter.can_two_hand = true;
+        fighter.shield_name = Some("Buckler".to_string());
+        fighter.shield_intact = true;
+        state.tick();
+        assert!(!state.combatants[0].two_hand_grip);
+
+        state.combatants[0].shield_intact = false;
+        state.tick();
+        state.tick();
+        let grips = state
+            .combat_log
+            .iter()
+            .flat_map(|entry| entry.events.iter())
+            .filter(|event| matches!(event, CombatEvent::TwoHandGrip { combatant: 0, .. }))
+            .count();
+        assert_eq!(grips, 1);
+        let fighter = &mut state.combatants[0];
+        assert!(fighter.two_hand_grip);
+        assert_eq!(fighter.weapon_speed, 10.0 + TWO_HAND_GRIP_SPEED);
+        assert_eq!(fighter.strength_damage, TWO_HAND_GRIP_DAMAGE);
+
+        fighter.reset_hp();
+        assert!(!fighter.two_hand_grip);
+        assert_eq!(fighter.weapon_speed, 10.0);
+        assert_eq!(fighter.strength_damage, 0);
+    }
+
+    fn battle_fighter(name: &str, hp: i32) -> Combatant {
+        Combatant {
+            name: name.to_string(),
+            weapon_name: "Test Weapon".to_string(),
+            damage_expr: "1d6".to_string(),
+            has_weapon: true,
+            max_hp: hp,
+            hp,
+            reach_ft: 3.0,
+            weapon_speed: 10.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn battle_ends_when_one_side_is_down() {
+        let mut state = SimState::new(SimConfig::new(20.0, 3.0));
+        let heroes = vec![battle_fighter("A", 60), battle_fighter("B", 60)];
+        let goblins = (1..=3)
+            .map(|n| battle_fighter(&format!("Goblin {n}"), 6))
+            .collect();
+        state.reset_with_sides([heroes, goblins]);
+        assert_eq!(state.sides(), vec![0, 0, 1, 1, 1]);
+        while !state.done && state.elapsed_seconds < 600 {
+            state.tick();
+        }
+        assert_eq!(state.winner(), Some(0));
+        assert!(state.combatants[2..].iter().all(|goblin| goblin.hp <= 0));
+        assert!(state.combatants[..2].iter().any(|hero| hero.hp > 0));
+    }
+
+    #[test]
+    fn fighters_spread_over_the_nearest_enemies_or_pick_the_weakest() {
+        let mut state = SimState::new(SimConfig::new(20.0, 3.0));
+        let side = vec![
+            battle_fighter("A", 20),
+            battle_fighter("B", 20),
+            battle_fighter("C", 20).with_target_priority(TargetPriority::Weakest),
+        ];
+        state.reset_with_sides([side, vec![battle_fighter("X", 20), battle_fighter("Y", 5)]]);
+        state.tick();
+        let targets: Vec<_> = state.actors.iter().map(|actor| actor.target).collect();
+        assert_eq!(targets[..3], [Some(3), Some(4), Some(4)]);
+        assert_eq!(targets[3..], [Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn outnumbered_defender_rolls_worse_dice_and_shields_only_the_front() {
+        let mut state = SimState::new(SimConfig::new(20.0, 3.0));
+        let mut defender = battle_fighter("Defender", 50);
+        defender.shield_name = Some("Buckler".to_string());
+        let attackers = (1..=5)
+            .map(|n| battle_fighter(&format!("Attacker {n}"), 50))
+            .collect();
+        state.reset_with_sides([vec![defender], attackers]);
+        state.config.rules.knockback = false;
+        state.actors[0].position = Point::new(10.0, 0.0);
+        state.actors[0].target = Some(1);
+        let spots = [
+            (11.0, 0.0),
+            (10.0, 1.0),
+            (10.0, -1.0),
+            (11.0, 0.5),
+            (9.0, 0.0),
+        ];
+        for (idx, (x, y)) in (1..=5).zip(spots) {
+            state.actors[idx].position = Point::new(x, y);
+            state.actors[idx].target = Some(0);
+        }
+        let die = |state: &SimState, attacker| state.attack_position(attacker, 0).defense_die();
+        assert_eq!(state.attack_position(1, 0).attackers, 5);
+        assert_eq!(die(&state, 1), (20, 0));
+        assert_eq!(state.attack_position(2, 0).angle, AttackAngle::Flank);
+        assert_eq!(die(&state, 3), (20, 0));
+        assert_eq!(die(&state, 4), (12, 0));
+        assert_eq!(state.attack_position(5, 0).angle, AttackAngle::Rear);
+        assert_eq!(die(&state, 5), (8, 0));
+
+        let events = state.strike(5, 0, 0, false);
+        let roll = events[0].attack_roll().unwrap();
+        assert_eq!(roll.defense_die_sides, 8);
+        assert_eq!(roll.shield_defense_bonus, 0);
+        assert!(roll.to_string().contains("from the rear"));
+        let events = state.strike(2, 0, 0, false);
+        assert_eq!(events[0].attack_roll().unwrap().shield_defense_bonus, 0);
+        let events = state.strike(1, 0, 0, false);
+        assert!(events[0].attack_roll().unwrap().shield_defense_bonus > 0);
+
+        for idx in 2..=5 {
+            state.combatants[idx].hp = 0;
+        }
+        assert_eq!(die(&state, 1), (20, 0));
+    }
+
+    #[test]
+    fn shielded_defender_keeps_the_full_die_against_two_front_attackers() {
+        let mut state = SimState::new(SimConfig::new(20.0, 3.0));
+        let mut defender = battle_fighter("Defender", 50);
+        defender.shield_name = Some("Buckler".to_string());
+        defender.shield_defense_bonus = 2;
+        state.reset_with_sides([
+            vec![defender],
+            vec![battle_fighter("A", 50), battle_fighter("B", 50)],
+        ]);
+        state.actors[0].position = Point::new(10.0, 0.0);
+        state.actors[0].target = Some(1);
+        state.actors[1].position = Point::new(11.0, 0.0);
+        state.actors[2].position = Point::new(11.0, 0.5);
+        for idx in 1..=2 {
+            state.actors[idx].target = Some(0);
+        }
+        let roll = state.strike(2, 0, 0, false)[0]
+            .attack_roll()
+            .unwrap()
+            .clone();
+        assert_eq!(roll.position.angle, AttackAngle::Front);
+        assert_eq!(roll.position.attackers, 2);
+        assert_eq!((roll.defense_die_sides, roll.defense_die_mod), (20, 0));
+        assert_eq!(roll.shield_defense_bonus, 4 + 2);
+    }
+
+    #[test]
+    fn turning_to_a_target_behind_takes_the_second() {
+        let mut state = tactics_duel(Arc::new(StandardTactics));
+        state.config.stop_distance = 3.0;
+        state.actors[1].position = Point::new(2.0, 0.0);
+        state.actors[0].facing = Point::new(0.0, 1.0);
+        state.combatants[0].initiative_count = Some(0.0);
+        state.combatants[0].next_attack_time = Some(0.0);
+        state.tick();
+        let events = &state.last_entry().unwrap().events;
+        assert!(matches!(events[0], CombatEvent::Turn { combatant: 0, .. }));
+        assert!(attacks_by(&state, 0).is_empty());
+        assert_eq!(state.actors[0].facing, Point::new(1.0, 0.0));
+        assert_eq!(state.combatants[0].next_attack_time, Some(1.0));
+
+        // Following a target that stays inside the front arc is free.
+        state.actors[1].position = Point::new(2.0, 1.0);
+        state.tick();
+        assert_eq!(attacks_by(&state, 0).len(), 1);
+        assert!(state.actors[0].facing.y > 0.0);
+    }
+
+    #[test]
+    fn walls_block_shots_and_cover_caps_them() {
+        let mut archer = battle_fighter("Archer", 50);
+        archer.weapon_name = "Longbow".to_string();
+        archer.attack_bonus = 100;
+        let mut target = battle_fighter("Target", 500);
+        target.move_speed = 0.0;
+        let barrier = |kind| TerrainFeature {
+            kind,
+            area: Area::Rect {
+                min: Point::new(9.0, -20.0),
+                max: Point::new(11.0, 20.0),
+            },
+        };
+        let mut state = SimState::new(SimConfig::new(20.0, 3.0))
+            .with_arena(Arena::new(vec![barrier(TerrainKind::Wall)]));
+        state.reset_with_sides([vec![archer], vec![target]]);
+        for _ in 0..30 {
+            state.tick();
+        }
+        assert!(attacks_by(&state, 0).is_empty());
+
+        state.arena = Arena::new(vec![barrier(TerrainKind::Cover { cover_value: 5 })]);
+        state.reset();
+        for _ in 0..30 {
+            state.tick();
+        }
+        let attacks = attacks_by(&state, 0);
+        assert!(!attacks.is_empty());
+        assert!(attacks.iter().all(|(_, roll)| roll.cover_cap == Some(5)));
+        assert!(attacks.iter().all(|(_, roll)| roll.attack_total == 5));
+    }
+
+    #[test]
+    fn doubling_dice_keeps_the_flat_bonus() {
+        assert_eq!(double_dice_expr("2d6p+3"), "4d6p+3");
+        assert_eq!(double_dice_expr("d4p+d6p"), "2d4p+2d6p");
+        assert_eq!(double_dice_expr("2d10p+3^2"), "4d10p+3");
+    }
+
     #[test]
     fn penetrating_roll_subtracts_one_on_extra_rolls() {
         let mut rolls = vec![6, 2].into_iter();
@@ -1512,7 +5083,15 @@ mod tests {
         );
         let mut state = make_state(attacker, defender);
         let mut rng = FixedRng(0);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
+        let _ = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
         assert!(!state.combatants[1].defense_plus_four_ready);
     }
 
@@ -1560,11 +5139,27 @@ mod tests {
         );
         let mut state = make_state(attacker, defender);
         let mut rng = FixedRng(0);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
+        let _ = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
         assert_eq!(state.combatants[1].hp, 20);
 
         let mut rng = FixedRng(0);
-        let _ = resolve_attack(&mut state.combatants, 0, 1, 0, false, &mut rng);
+        let _ = resolve_attack(
+            &mut state.combatants,
+            0,
+            1,
+            0,
+            false,
+            RuleToggles::default(),
+            &mut rng,
+        );
         assert_eq!(state.combatants[1].hp, 20);
     }
 }
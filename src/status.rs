@@ -0,0 +1,224 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Conditions a fighter can be under during a duel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusKind {
+    Prone,
+    Stunned,
+    Incapacitated,
+    HeldAtBay,
+    /// Skipped an attack to parry; lasts until the next attack would have come due.
+    FullParry,
+    /// Just charged into contact; the fighter's Dex bonus to defense is lost.
+    Charging,
+}
+
+/// What a status does to the fighter carrying it. Modifiers from several statuses add up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatusModifiers {
+    pub attack: i32,
+    pub defense: i32,
+    /// Seconds added to the fighter's weapon speed.
+    pub speed: f32,
+    /// Fraction of normal movement; 0 pins the fighter in place.
+    pub movement: f32,
+    /// False while the fighter can take no actions at all.
+    pub can_act: bool,
+}
+
+impl Default for StatusModifiers {
+    fn default() -> Self {
+        Self {
+            attack: 0,
+            defense: 0,
+            speed: 0.0,
+            movement: 1.0,
+            can_act: true,
+        }
+    }
+}
+
+impl StatusModifiers {
+    fn combine(self, other: Self) -> Self {
+        Self {
+            attack: self.attack + other.attack,
+            defense: self.defense + other.defense,
+            speed: self.speed + other.speed,
+            movement: self.movement.min(other.movement),
+            can_act: self.can_act && other.can_act,
+        }
+    }
+}
+
+impl StatusKind {
+    pub const ALL: [StatusKind; 6] = [
+        StatusKind::Prone,
+        StatusKind::Stunned,
+        StatusKind::Incapacitated,
+        StatusKind::HeldAtBay,
+        StatusKind::FullParry,
+        StatusKind::Charging,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StatusKind::Prone => "Prone",
+            StatusKind::Stunned => "Stunned",
+            StatusKind::Incapacitated => "Incapacitated",
+            StatusKind::HeldAtBay => "Held at bay",
+            StatusKind::FullParry => "Full parry",
+            StatusKind::Charging => "Charging",
+        }
+    }
+
+    pub fn modifiers(self) -> StatusModifiers {
+        match self {
+            // Standing up is the fighter's next action; until then they fight from the ground.
+            StatusKind::Prone => StatusModifiers {
+                attack: -4,
+                defense: -4,
+                movement: 0.0,
+                ..Default::default()
+            },
+            StatusKind::Stunned => StatusModifiers {
+                defense: -4,
+                movement: 0.5,
+                can_act: false,
+                ..Default::default()
+            },
+            StatusKind::Incapacitated => StatusModifiers {
+                defense: -4,
+                movement: 0.0,
+                can_act: false,
+                ..Default::default()
+            },
+            // The reach weapon keeps them from closing until they knock it aside; their
+            // attacks go into knock-aside attempts instead.
+            StatusKind::HeldAtBay => StatusModifiers {
+                movement: 0.0,
+                ..Default::default()
+            },
+            StatusKind::FullParry => StatusModifiers {
+                defense: 5,
+                ..Default::default()
+            },
+            // The lost Dex bonus differs per fighter, so the sim applies it directly.
+            StatusKind::Charging => StatusModifiers::default(),
+        }
+    }
+}
+
+impl fmt::Display for StatusKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// A status and the sim second it wears off.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub until: f32,
+}
+
+/// The statuses currently on one fighter, at most one of each kind.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Statuses {
+    effects: Vec<StatusEffect>,
+}
+
+impl Statuses {
+    /// Adds a status, or extends it if the fighter already has it.
+    pub fn apply(&mut self, kind: StatusKind, until: f32) {
+        match self.effects.iter_mut().find(|effect| effect.kind == kind) {
+            Some(effect) => effect.until = effect.until.max(until),
+            None => self.effects.push(StatusEffect { kind, until }),
+        }
+    }
+
+    pub fn remove(&mut self, kind: StatusKind) {
+        self.effects.retain(|effect| effect.kind != kind);
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.until(kind).is_some()
+    }
+
+    pub fn until(&self, kind: StatusKind) -> Option<f32> {
+        self.effects
+            .iter()
+            .find(|effect| effect.kind == kind)
+            .map(|effect| effect.until)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.effects.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    pub fn modifiers(&self) -> StatusModifiers {
+        self.effects
+            .iter()
+            .fold(StatusModifiers::default(), |total, effect| {
+                total.combine(effect.kind.modifiers())
+            })
+    }
+
+    /// Drops every status that has run out by `now`, returning what ended.
+    pub fn expire(&mut self, now: f32) -> Vec<StatusKind> {
+        let mut ended = Vec::new();
+        self.effects.retain(|effect| {
+            let active = now < effect.until;
+            if !active {
+                ended.push(effect.kind);
+            }
+            active
+        });
+        ended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reapplying_a_status_extends_it() {
+        let mut statuses = Statuses::default();
+        statuses.apply(StatusKind::Prone, 5.0);
+        statuses.apply(StatusKind::Prone, 3.0);
+        assert_eq!(statuses.until(StatusKind::Prone), Some(5.0));
+        statuses.apply(StatusKind::Prone, 7.0);
+        assert_eq!(statuses.until(StatusKind::Prone), Some(7.0));
+        assert_eq!(statuses.iter().count(), 1);
+    }
+
+    #[test]
+    fn modifiers_stack_and_statuses_expire_in_order() {
+        let mut statuses = Statuses::default();
+        statuses.apply(StatusKind::Prone, 4.0);
+        statuses.apply(StatusKind::Stunned, 6.0);
+        let modifiers = statuses.modifiers();
+        assert_eq!(modifiers.attack, -4);
+        assert_eq!(modifiers.defense, -8);
+        assert_eq!(modifiers.movement, 0.0);
+        assert!(!modifiers.can_act);
+
+        assert!(statuses.expire(3.0).is_empty());
+        assert_eq!(statuses.expire(4.0), vec![StatusKind::Prone]);
+        assert_eq!(statuses.modifiers().movement, 0.5);
+        assert_eq!(statuses.expire(6.0), vec![StatusKind::Stunned]);
+        assert!(statuses.is_empty());
+        assert_eq!(statuses.modifiers(), StatusModifiers::default());
+    }
+}
@@ -0,0 +1,505 @@
+//! Tactics written as ordered rules in build and scenario files, e.g.
+//! `if hp < 30% then fight defensively 4` or `if shield broken then switch to two-hand grip`.
+//!
+//! A rule is `if <condition> [and <condition>...] then <action>[, <action>...]`.
+//! Conditions compare `hp`, `reach`, `speed` (each optionally prefixed with `opponent`),
+//! `distance` or `time` against a number, a percentage of max hp, `mine`, `theirs` or
+//! another quantity, or test a flag: `[not] [opponent] shield broken`, `engaged`,
+//! `ranged`, `weapon swapped`, `two-hand grip` or a status such as `prone`.
+//! Actions are a movement (`advance`, `hold`, `retreat`), a turn action (`attack`,
+//! `full parry`, `jab`, `swap weapon`), a stance (`normal`, `aggressive attack`,
+//! `fight defensively 1`-`4`), `charge` or `two-hand grip`.
+
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::maneuver::{Stance, TurnAction};
+use crate::sim::{Combatant, max_range_for_weapon};
+use crate::status::StatusKind;
+use crate::tactics::{Intent, Movement, Situation, Tactics};
+
+/// One `if ... then ...` line, kept with its source text so builds round-trip.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TacticRule {
+    text: String,
+    conditions: Vec<Condition>,
+    actions: Vec<RuleAction>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Mine,
+    Opponent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Quantity {
+    Hp(Side),
+    Reach(Side),
+    Speed(Side),
+    Distance,
+    Time,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand {
+    Number(f32),
+    /// Fraction of the compared fighter's max hp.
+    Percent(f32),
+    Quantity(Quantity),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Flag {
+    ShieldBroken,
+    Engaged,
+    Ranged,
+    WeaponSwapped,
+    TwoHandGrip,
+    Status(StatusKind),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Condition {
+    Compare {
+        quantity: Quantity,
+        comparison: Comparison,
+        operand: Operand,
+    },
+    Flag {
+        side: Side,
+        flag: Flag,
+        negated: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RuleAction {
+    Move(Movement),
+    Act(TurnAction),
+    Stance(Stance),
+    Charge,
+    TwoHandGrip,
+}
+
+/// Longest first, so `<=` is not read as `<`.
+const COMPARISONS: [(&str, Comparison); 7] = [
+    ("<=", Comparison::LessOrEqual),
+    (">=", Comparison::GreaterOrEqual),
+    ("!=", Comparison::NotEqual),
+    ("==", Comparison::Equal),
+    ("<", Comparison::Less),
+    (">", Comparison::Greater),
+    ("=", Comparison::Equal),
+];
+
+impl TacticRule {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let normalized = normalize(text);
+        let body = normalized
+            .strip_prefix("if ")
+            .ok_or_else(|| format!("{text:?}: a rule starts with \"if\""))?;
+        let (conditions, actions) = body
+            .split_once(" then ")
+            .ok_or_else(|| format!("{text:?}: missing \"then\""))?;
+        let conditions = conditions
+            .split(" and ")
+            .map(parse_condition)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("{text:?}: {err}"))?;
+        let actions = actions
+            .split(',')
+            .flat_map(|part| part.split(" and "))
+            .map(parse_action)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("{text:?}: {err}"))?;
+        Ok(Self {
+            text: text.trim().to_string(),
+            conditions,
+            actions,
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn matches(&self, situation: &Situation) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.holds(situation))
+    }
+
+    fn apply(&self, intent: &mut Intent) {
+        for action in &self.actions {
+            match *action {
+                RuleAction::Move(movement) => intent.movement = movement,
+                RuleAction::Act(action) => intent.action = action,
+                RuleAction::Stance(stance) => intent.stance = stance,
+                RuleAction::Charge => intent.charge = true,
+                RuleAction::TwoHandGrip => intent.two_hand_grip = true,
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for TacticRule {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        Self::parse(&text)
+    }
+}
+
+impl From<TacticRule> for String {
+    fn from(rule: TacticRule) -> Self {
+        rule.text
+    }
+}
+
+impl fmt::Display for TacticRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Lowercase, single-spaced, with hyphens read as spaces (`two-hand` = `two hand`).
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .replace('-', " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_condition(text: &str) -> Result<Condition, String> {
+    let text = text.trim();
+    if let Some((symbol, comparison)) = COMPARISONS.iter().find(|(symbol, _)| text.contains(symbol))
+    {
+        let (left, right) = text.split_once(symbol).unwrap();
+        let quantity = parse_quantity(left)?;
+        let operand = parse_operand(right, quantity)?;
+        return Ok(Condition::Compare {
+            quantity,
+            comparison: *comparison,
+            operand,
+        });
+    }
+    let mut words: Vec<&str> = text
+        .split(' ')
+        .filter(|word| !matches!(*word, "is" | "has" | "in" | "a"))
+        .collect();
+    let negated = words.first() == Some(&"not");
+    if negated {
+        words.remove(0);
+    }
+    let side = if words.first() == Some(&"opponent") {
+        words.remove(0);
+        Side::Opponent
+    } else {
+        Side::Mine
+    };
+    let flag = match words.join(" ").as_str() {
+        "shield broken" | "broken shield" => Flag::ShieldBroken,
+        "engaged" => Flag::Engaged,
+        "ranged" => Flag::Ranged,
+        "weapon swapped" | "swapped" => Flag::WeaponSwapped,
+        "two hand grip" | "two handed" => Flag::TwoHandGrip,
+        name => StatusKind::ALL
+            .into_iter()
+            .find(|status| normalize(status.label()) == name)
+            .map(Flag::Status)
+            .ok_or_else(|| format!("unknown condition {text:?}"))?,
+    };
+    Ok(Condition::Flag {
+        side,
+        flag,
+        negated,
+    })
+}
+
+fn parse_quantity(text: &str) -> Result<Quantity, String> {
+    let text = text.trim();
+    let (side, name) = if let Some(name) = text.strip_prefix("opponent ") {
+        (Side::Opponent, name)
+    } else {
+        (Side::Mine, text.strip_prefix("my ").unwrap_or(text))
+    };
+    match (name, side) {
+        ("hp", _) => Ok(Quantity::Hp(side)),
+        ("reach", _) => Ok(Quantity::Reach(side)),
+        ("speed" | "weapon speed", _) => Ok(Quantity::Speed(side)),
+        ("distance", Side::Mine) => Ok(Quantity::Distance),
+        ("time", Side::Mine) => Ok(Quantity::Time),
+        _ => Err(format!("unknown quantity {text:?}")),
+    }
+}
+
+fn parse_operand(text: &str, quantity: Quantity) -> Result<Operand, String> {
+    let text = text.trim();
+    let same_for = |side| match quantity {
+        Quantity::Hp(_) => Ok(Quantity::Hp(side)),
+        Quantity::Reach(_) => Ok(Quantity::Reach(side)),
+        Quantity::Speed(_) => Ok(Quantity::Speed(side)),
+        Quantity::Distance | Quantity::Time => Err(format!(
+            "{text:?} only compares fighters' hp, reach or speed"
+        )),
+    };
+    if text == "mine" {
+        return same_for(Side::Mine).map(Operand::Quantity);
+    }
+    if text == "theirs" || text == "opponent's" {
+        return same_for(Side::Opponent).map(Operand::Quantity);
+    }
+    if let Some(percent) = text.strip_suffix('%') {
+        if !matches!(quantity, Quantity::Hp(_)) {
+            return Err(format!("{text:?}: percentages only apply to hp"));
+        }
+        return percent
+            .trim()
+            .parse::<f32>()
+            .map(|percent| Operand::Percent(percent / 100.0))
+            .map_err(|_| format!("bad percentage {text:?}"));
+    }
+    if let Ok(number) = text.parse::<f32>() {
+        return Ok(Operand::Number(number));
+    }
+    parse_quantity(text).map(Operand::Quantity)
+}
+
+fn parse_action(text: &str) -> Result<RuleAction, String> {
+    let text = text.trim();
+    let name = ["switch to ", "take ", "use "]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix))
+        .unwrap_or(text);
+    let action = match name {
+        "advance" => RuleAction::Move(Movement::Advance),
+        "hold" | "hold ground" => RuleAction::Move(Movement::Hold),
+        "retreat" => RuleAction::Move(Movement::Retreat),
+        "attack" => RuleAction::Act(TurnAction::Attack),
+        "full parry" => RuleAction::Act(TurnAction::FullParry),
+        "jab" => RuleAction::Act(TurnAction::Jab),
+        "swap weapon" | "reserve weapon" => RuleAction::Act(TurnAction::SwapWeapon),
+        "normal" | "normal stance" => RuleAction::Stance(Stance::Normal),
+        "aggressive attack" => RuleAction::Stance(Stance::AggressiveAttack),
+        "charge" => RuleAction::Charge,
+        "two hand grip" | "two handed grip" => RuleAction::TwoHandGrip,
+        _ => {
+            let level = name
+                .strip_prefix("fight defensively ")
+                .and_then(|level| level.parse::<u8>().ok())
+                .filter(|level| (1..=4).contains(level))
+                .ok_or_else(|| format!("unknown action {text:?}"))?;
+            RuleAction::Stance(Stance::FightDefensively(level))
+        }
+    };
+    Ok(action)
+}
+
+impl Condition {
+    fn holds(&self, situation: &Situation) -> bool {
+        match *self {
+            Condition::Compare {
+                quantity,
+                comparison,
+                operand,
+            } => {
+                let left = quantity.value(situation);
+                let right = match operand {
+                    Operand::Number(number) => number,
+                    Operand::Percent(fraction) => match quantity {
+                        Quantity::Hp(side) => fighter(situation, side).max_hp as f32 * fraction,
+                        _ => return false,
+                    },
+                    Operand::Quantity(other) => other.value(situation),
+                };
+                match comparison {
+                    Comparison::Less => left < right,
+                    Comparison::LessOrEqual => left <= right,
+                    Comparison::Greater => left > right,
+                    Comparison::GreaterOrEqual => left >= right,
+                    Comparison::Equal => left == right,
+                    Comparison::NotEqual => left != right,
+                }
+            }
+            Condition::Flag {
+                side,
+                flag,
+                negated,
+            } => flag.holds(situation, fighter(situation, side)) != negated,
+        }
+    }
+}
+
+impl Quantity {
+    fn value(self, situation: &Situation) -> f32 {
+        match self {
+            Quantity::Hp(side) => fighter(situation, side).hp as f32,
+            Quantity::Reach(side) => fighter(situation, side).reach_ft.max(1.0),
+            Quantity::Speed(side) => fighter(situation, side).weapon_speed,
+            Quantity::Distance => situation.distance,
+            Quantity::Time => situation.now,
+        }
+    }
+}
+
+impl Flag {
+    fn holds(self, situation: &Situation, fighter: &Combatant) -> bool {
+        match self {
+            Flag::ShieldBroken => fighter.shield_name.is_some() && !fighter.shield_intact,
+            Flag::Engaged => situation.engaged(),
+            Flag::Ranged => max_range_for_weapon(&fighter.weapon_name).is_some(),
+            Flag::WeaponSwapped => fighter.weapon_swapped,
+            Flag::TwoHandGrip => fighter.two_hand_grip,
+            Flag::Status(status) => fighter.statuses.has(status),
+        }
+    }
+}
+
+fn fighter<'a>(situation: &Situation<'a>, side: Side) -> &'a Combatant {
+    match side {
+        Side::Mine => situation.me,
+        Side::Opponent => situation.opponent,
+    }
+}
+
+/// Ordered rules layered over another policy. Each part of the intent comes from the
+/// first matching rule that sets it, or from the fallback when none does.
+#[derive(Debug)]
+pub struct ScriptedTactics {
+    rules: Vec<TacticRule>,
+    fallback: Arc<dyn Tactics>,
+}
+
+impl ScriptedTactics {
+    pub fn new(rules: Vec<TacticRule>, fallback: Arc<dyn Tactics>) -> Self {
+        Self { rules, fallback }
+    }
+}
+
+impl Tactics for ScriptedTactics {
+    fn name(&self) -> &str {
+        "Scripted"
+    }
+
+    fn decide(&self, situation: &Situation) -> Intent {
+        let mut intent = self.fallback.decide(situation);
+        // Applied last to first, so earlier rules overwrite later ones.
+        for rule in self.rules.iter().rev() {
+            if rule.matches(situation) {
+                rule.apply(&mut intent);
+            }
+        }
+        intent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tactics::StandardTactics;
+
+    fn situation<'a>(me: &'a Combatant, opponent: &'a Combatant) -> Situation<'a> {
+        Situation {
+            me,
+            opponent,
+            distance: 3.0,
+            now: 0.0,
+        }
+    }
+
+    fn scripted(rules: &[&str]) -> ScriptedTactics {
+        let rules = rules
+            .iter()
+            .map(|rule| TacticRule::parse(rule).unwrap())
+            .collect();
+        ScriptedTactics::new(rules, Arc::new(StandardTactics))
+    }
+
+    #[test]
+    fn rules_from_the_request_parse_and_fire() {
+        let tactics = scripted(&[
+            "if hp < 30% then Fight Defensively 4",
+            "if opponent reach > mine then Charge",
+            "if shield broken then switch to two-hand grip",
+        ]);
+        let mut me = Combatant {
+            reach_ft: 3.0,
+            shield_name: Some("Buckler".to_string()),
+            shield_intact: true,
+            ..Default::default()
+        };
+        let opponent = Combatant {
+            reach_ft: 10.0,
+            ..Default::default()
+        };
+        let intent = tactics.decide(&situation(&me, &opponent));
+        assert_eq!(intent.stance, Stance::Normal);
+        assert!(intent.charge && !intent.two_hand_grip);
+
+        me.hp = 2;
+        me.shield_intact = false;
+        me.reach_ft = 10.0;
+        let intent = tactics.decide(&situation(&me, &opponent));
+        assert_eq!(intent.stance, Stance::FightDefensively(4));
+        assert!(!intent.charge && intent.two_hand_grip);
+    }
+
+    #[test]
+    fn earlier_rules_take_precedence() {
+        let tactics = scripted(&[
+            "if opponent prone then aggressive attack, advance",
+            "if distance <= 5 then full parry and fight defensively 2",
+        ]);
+        let me = Combatant {
+            reach_ft: 5.0,
+            ..Default::default()
+        };
+        let mut opponent = Combatant::default();
+        let intent = tactics.decide(&situation(&me, &opponent));
+        assert_eq!(intent.action, TurnAction::FullParry);
+        assert_eq!(intent.stance, Stance::FightDefensively(2));
+        assert_eq!(intent.movement, Movement::Hold);
+
+        opponent.statuses.apply(StatusKind::Prone, 10.0);
+        let intent = tactics.decide(&situation(&me, &opponent));
+        assert_eq!(intent.action, TurnAction::FullParry);
+        assert_eq!(intent.stance, Stance::AggressiveAttack);
+        assert_eq!(intent.movement, Movement::Advance);
+    }
+
+    #[test]
+    fn bad_rules_are_reported_with_their_text() {
+        for (rule, error) in [
+            ("hp < 3 then jab", "starts with \"if\""),
+            ("if hp < 3 jab", "missing \"then\""),
+            ("if mana < 3 then jab", "unknown quantity"),
+            ("if distance < 50% then jab", "percentages only apply to hp"),
+            ("if not flying then jab", "unknown condition"),
+            ("if hp < 3 then fight defensively 5", "unknown action"),
+        ] {
+            let err = TacticRule::parse(rule).unwrap_err();
+            assert!(err.contains(rule) && err.contains(error), "{err}");
+        }
+        let rule: TacticRule =
+            serde_json::from_str("\"If Opponent HP >= theirs then jab\"").unwrap();
+        assert_eq!(
+            serde_json::to_string(&rule).unwrap(),
+            "\"If Opponent HP >= theirs then jab\""
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::maneuver::{Stance, TurnAction};
+use crate::sim::{Combatant, max_range_for_weapon};
+
+/// What a fighter does with their feet this second.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Movement {
+    /// Close on the opponent until they are within the fighter's reach.
+    Advance,
+    #[default]
+    Hold,
+    /// Back away a walking step.
+    Retreat,
+}
+
+/// A fighter's decision for one second of the count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Intent {
+    pub movement: Movement,
+    /// Used if the fighter's attack comes due this second.
+    pub action: TurnAction,
+    pub stance: Stance,
+    /// Declare a charge on making contact after a long enough run.
+    pub charge: bool,
+    /// Take a two-handed grip on the weapon, if it allows one and no shield is in the way.
+    pub two_hand_grip: bool,
+}
+
+impl Intent {
+    /// The fighter's configured behavior with the given movement.
+    pub fn configured(me: &Combatant, movement: Movement) -> Self {
+        Self {
+            movement,
+            action: me.turn_action,
+            stance: me.stance,
+            charge: me.charge,
+            two_hand_grip: false,
+        }
+    }
+}
+
+/// What a fighter can see when deciding what to do.
+#[derive(Clone, Copy, Debug)]
+pub struct Situation<'a> {
+    pub me: &'a Combatant,
+    /// The fighter's current target.
+    pub opponent: &'a Combatant,
+    pub distance: f32,
+    /// Current second of the count.
+    pub now: f32,
+}
+
+impl Situation<'_> {
+    pub fn my_reach(&self) -> f32 {
+        self.me.reach_ft.max(1.0)
+    }
+
+    pub fn opponent_reach(&self) -> f32 {
+        self.opponent.reach_ft.max(1.0)
+    }
+
+    pub fn my_range(&self) -> Option<f32> {
+        max_range_for_weapon(&self.me.weapon_name)
+    }
+
+    pub fn opponent_is_ranged(&self) -> bool {
+        max_range_for_weapon(&self.opponent.weapon_name).is_some()
+    }
+
+    /// Both fighters are within each other's reach.
+    pub fn engaged(&self) -> bool {
+        self.distance <= self.my_reach().min(self.opponent_reach())
+    }
+
+    pub fn hp_fraction(&self) -> f32 {
+        self.me.hp as f32 / self.me.max_hp.max(1) as f32
+    }
+}
+
+/// A decision policy consulted once per second for each fighter.
+pub trait Tactics: fmt::Debug + Send + Sync {
+    fn name(&self) -> &str;
+
+    fn decide(&self, situation: &Situation) -> Intent;
+}
+
+/// The sim's stock behavior: melee fighters walk in until in reach, ranged fighters
+/// back-step while the target is in range, and the fighter's configured stance and
+/// turn action are used as set.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StandardTactics;
+
+impl Tactics for StandardTactics {
+    fn name(&self) -> &str {
+        "Standard"
+    }
+
+    fn decide(&self, situation: &Situation) -> Intent {
+        Intent::configured(situation.me, standard_movement(situation))
+    }
+}
+
+fn standard_movement(situation: &Situation) -> Movement {
+    match situation.my_range() {
+        Some(_) if situation.engaged() => Movement::Hold,
+        Some(range) if situation.distance <= range => Movement::Retreat,
+        Some(_) => Movement::Advance,
+        None if waits_for_charge(situation.me, situation.opponent) => Movement::Hold,
+        None if situation.distance > situation.my_reach() => Movement::Advance,
+        None => Movement::Hold,
+    }
+}
+
+/// A fighter set for charge holds their ground while a charging opponent comes to them.
+pub fn waits_for_charge(combatant: &Combatant, opponent: &Combatant) -> bool {
+    combatant.set_for_charge && !combatant.charge && opponent.charge
+}
+
+/// Keeps a shorter-reach opponent at the tip of the weapon, stepping back when they get
+/// inside it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KiteTactics;
+
+impl Tactics for KiteTactics {
+    fn name(&self) -> &str {
+        "Kite"
+    }
+
+    fn decide(&self, situation: &Situation) -> Intent {
+        let movement = if situation.my_range().is_some() {
+            standard_movement(situation)
+        } else if situation.distance > situation.my_reach() {
+            Movement::Advance
+        } else if situation.opponent_reach() < situation.my_reach()
+            && situation.distance <= situation.opponent_reach()
+        {
+            Movement::Retreat
+        } else {
+            Movement::Hold
+        };
+        Intent {
+            action: TurnAction::Attack,
+            ..Intent::configured(situation.me, movement)
+        }
+    }
+}
+
+/// Lets the opponent come, fights defensively, and parries outright once badly hurt.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TurtleTactics;
+
+impl Tactics for TurtleTactics {
+    fn name(&self) -> &str {
+        "Turtle"
+    }
+
+    fn decide(&self, situation: &Situation) -> Intent {
+        let movement = if situation.opponent_is_ranged() {
+            standard_movement(situation)
+        } else {
+            Movement::Hold
+        };
+        let action = if situation.hp_fraction() < 0.5 {
+            TurnAction::FullParry
+        } else {
+            TurnAction::Attack
+        };
+        Intent {
+            action,
+            stance: Stance::FightDefensively(2),
+            ..Intent::configured(situation.me, movement)
+        }
+    }
+}
+
+/// Presses in and attacks aggressively every time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BerserkTactics;
+
+impl Tactics for BerserkTactics {
+    fn name(&self) -> &str {
+        "Berserk"
+    }
+
+    fn decide(&self, situation: &Situation) -> Intent {
+        let movement = if situation.distance > situation.my_reach() {
+            Movement::Advance
+        } else {
+            Movement::Hold
+        };
+        Intent {
+            action: TurnAction::Attack,
+            stance: Stance::AggressiveAttack,
+            ..Intent::configured(situation.me, movement)
+        }
+    }
+}
+
+/// Which enemy a fighter in a battle goes after when they need a new target.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetPriority {
+    #[default]
+    Nearest,
+    /// Fewest hit points left.
+    Weakest,
+    /// Most hit points left.
+    Strongest,
+}
+
+impl TargetPriority {
+    pub const ALL: [TargetPriority; 3] = [
+        TargetPriority::Nearest,
+        TargetPriority::Weakest,
+        TargetPriority::Strongest,
+    ];
+}
+
+impl fmt::Display for TargetPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetPriority::Nearest => write!(f, "Nearest"),
+            TargetPriority::Weakest => write!(f, "Weakest"),
+            TargetPriority::Strongest => write!(f, "Strongest"),
+        }
+    }
+}
+
+/// The built-in tactics, as chosen in builds and the GUI.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TacticsKind {
+    #[default]
+    Standard,
+    Kite,
+    Turtle,
+    Berserk,
+}
+
+impl TacticsKind {
+    pub const ALL: [TacticsKind; 4] = [
+        TacticsKind::Standard,
+        TacticsKind::Kite,
+        TacticsKind::Turtle,
+        TacticsKind::Berserk,
+    ];
+
+    pub fn tactics(self) -> Arc<dyn Tactics> {
+        match self {
+            TacticsKind::Standard => Arc::new(StandardTactics),
+            TacticsKind::Kite => Arc::new(KiteTactics),
+            TacticsKind::Turtle => Arc::new(TurtleTactics),
+            TacticsKind::Berserk => Arc::new(BerserkTactics),
+        }
+    }
+}
+
+impl fmt::Display for TacticsKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tactics().name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn situation<'a>(me: &'a Combatant, opponent: &'a Combatant, distance: f32) -> Situation<'a> {
+        Situation {
+            me,
+            opponent,
+            distance,
+            now: 0.0,
+        }
+    }
+
+    #[test]
+    fn kite_steps_back_when_a_shorter_weapon_gets_inside() {
+        let pike = Combatant {
+            reach_ft: 10.0,
+            ..Default::default()
+        };
+        let sword = Combatant {
+            reach_ft: 3.0,
+            ..Default::default()
+        };
+        let decide = |distance| KiteTactics.decide(&situation(&pike, &sword, distance));
+        assert_eq!(decide(20.0).movement, Movement::Advance);
+        assert_eq!(decide(8.0).movement, Movement::Hold);
+        assert_eq!(decide(3.0).movement, Movement::Retreat);
+        assert_eq!(
+            StandardTactics
+                .decide(&situation(&pike, &sword, 3.0))
+                .movement,
+            Movement::Hold
+        );
+    }
+
+    #[test]
+    fn turtle_parries_once_badly_hurt() {
+        let mut me = Combatant::default();
+        let opponent = Combatant::default();
+        let intent = TurtleTactics.decide(&situation(&me, &opponent, 1.0));
+        assert_eq!(intent.action, TurnAction::Attack);
+        assert_eq!(intent.stance, Stance::FightDefensively(2));
+        me.hp = 4;
+        let intent = TurtleTactics.decide(&situation(&me, &opponent, 1.0));
+        assert_eq!(intent.action, TurnAction::FullParry);
+        assert_eq!(intent.movement, Movement::Hold);
+    }
+}